@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use super::Object;
+
+/// The hashable form of a map key. Only numbers, strings, booleans and `nil` can be keys, and two
+/// keys are the same key when they compare equal with `==`, except that every NaN is the same key.
+#[derive(PartialEq, Eq, Hash)]
+pub(crate) enum MapKey {
+    // NOTE: the bits of the number, with `-0` turned into `0` and every NaN into the same NaN.
+    Number(u64),
+    String(Bytes),
+    Boolean(bool),
+    Nil,
+}
+
+impl MapKey {
+    /// The key for `object`, `None` if it can't be hashed.
+    pub(crate) fn new(object: &Object) -> Option<Self> {
+        let key = match object {
+            Object::Number(v) if v.is_nan() => MapKey::Number(f64::NAN.to_bits()),
+            Object::Number(v) if *v == 0.0 => MapKey::Number(0f64.to_bits()),
+            Object::Number(v) => MapKey::Number(v.to_bits()),
+            Object::String(bytes) => MapKey::String(bytes.clone()),
+            Object::Boolean(v) => MapKey::Boolean(*v),
+            Object::Nil => MapKey::Nil,
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+/// A map that remembers the order its keys were first inserted in, which is the order it prints
+/// its entries in.
+#[derive(Default)]
+pub(crate) struct Map {
+    entries: Vec<(Object, Object)>,
+    positions: HashMap<MapKey, usize>,
+}
+
+impl Map {
+    pub(crate) fn get(&self, key: &MapKey) -> Option<&Object> {
+        let position = *self.positions.get(key)?;
+        Some(&self.entries[position].1)
+    }
+
+    /// Sets the value of the key, keeping its position if it is already in the map.
+    pub(crate) fn insert(&mut self, key: MapKey, key_object: Object, value: Object) {
+        match self.positions.get(&key) {
+            Some(position) => self.entries[*position].1 = value,
+            None => {
+                self.positions.insert(key, self.entries.len());
+                self.entries.push((key_object, value));
+            }
+        }
+    }
+
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &(Object, Object)> {
+        self.entries.iter()
+    }
+}
@@ -1,22 +1,34 @@
 #![allow(dead_code, unused_variables)]
 
-use std::{cell::RefCell, collections::HashMap, io::Write, rc::Rc};
+use std::{
+    cell::RefCell,
+    io::{Read, Write},
+    rc::Rc,
+};
 
 use bytes::{BufMut, Bytes, BytesMut};
+pub(crate) mod map;
 pub(crate) mod native;
 
+use map::{Map, MapKey};
+
 use crate::{
     parser::{
         expression::{
-            CallExpression, Expression, FunctionExpression, IfStatement, Precedence, Statement,
+            CallExpression, ClassDeclaration, EnumDeclaration, Expression, ExpressionKind,
+            FunctionExpression, IfStatement, MatchStatement, Pattern, Statement, StatementKind,
             VarDeclaration, WhileLoop,
         },
         ParseError, Parser,
     },
-    token::Token,
-    Void,
+    symbol::{Symbol, SymbolMap},
+    token::{Span, Token},
+    Completion,
+};
+use crate::{
+    Either,
+    Either::{Left, Right},
 };
-use crate::{Either, Either::Right};
 
 #[derive(Clone)]
 pub(crate) enum Object {
@@ -25,6 +37,14 @@ pub(crate) enum Object {
     String(Bytes),
     Function(Function),
     NativeFunction(Rc<dyn Fn(Option<Box<dyn Iterator<Item = Object>>>) -> Object>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    // NOTE: shared, assigning a list or passing it to a function doesn't copy it.
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<Map>>),
+    // NOTE: a variant with a payload, calling it creates an `EnumValue`.
+    EnumConstructor(Rc<Variant>),
+    EnumValue(Rc<EnumValue>),
     Nil,
 }
 
@@ -32,6 +52,46 @@ pub(crate) enum Object {
 pub(crate) struct Function {
     fe: Rc<FunctionExpression>,
     env: Env,
+    // NOTE: an initializer always returns the instance it is bound to, whatever its body returns.
+    is_initializer: bool,
+}
+
+pub(crate) struct Class {
+    name: Bytes,
+    methods: SymbolMap<Rc<FunctionExpression>>,
+    // NOTE: the environment the class is declared in, which its methods close over.
+    env: Env,
+}
+
+pub(crate) struct Instance {
+    class: Rc<Class>,
+    fields: SymbolMap<Object>,
+}
+
+pub(crate) struct Variant {
+    name: Bytes,
+    arity: usize,
+}
+
+pub(crate) struct EnumValue {
+    variant: Rc<Variant>,
+    payload: Vec<Object>,
+}
+
+/// The target of an assignment, with its object and index already evaluated.
+enum Place<'a> {
+    Variable {
+        symbol: Symbol,
+        name: &'a Bytes,
+    },
+    Field {
+        instance: Rc<RefCell<Instance>>,
+        property: &'a Token,
+    },
+    Index {
+        container: Object,
+        index: Object,
+    },
 }
 
 impl std::fmt::Debug for Object {
@@ -46,6 +106,12 @@ impl std::fmt::Debug for Object {
             }
             Object::Function(fe) => write!(f, "{fe:?}", fe = fe.fe.as_ref()),
             Object::NativeFunction(_) => write!(f, "<native fn>"),
+            Object::Class(_)
+            | Object::Instance(_)
+            | Object::List(_)
+            | Object::Map(_)
+            | Object::EnumConstructor(_)
+            | Object::EnumValue(_) => write!(f, "{self}"),
         }
     }
 }
@@ -59,12 +125,30 @@ impl Object {
             Object::Nil => false,
             Object::Function(_) => true,
             Object::NativeFunction(_) => true,
+            Object::Class(_) => true,
+            Object::Instance(_) => true,
+            Object::List(_) => true,
+            Object::Map(_) => true,
+            Object::EnumConstructor(_) => true,
+            Object::EnumValue(_) => true,
         }
     }
 }
 
 impl std::fmt::Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, &mut vec![])
+    }
+}
+
+impl Object {
+    /// `printing` holds the lists and maps being printed further up, so a list or map that
+    /// contains itself prints as `[...]` or `{...}` instead of recursing forever.
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        printing: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
         match self {
             Object::Number(v) => write!(f, "{}", v),
             Object::Boolean(v) => write!(f, "{}", v),
@@ -75,32 +159,117 @@ impl std::fmt::Display for Object {
             }
             Object::Function(fe) => write!(f, "{fe:?}", fe = fe.fe.as_ref()),
             Object::NativeFunction(_) => write!(f, "<native fn>"),
+            Object::Class(class) => {
+                write!(f, "<class {}>", String::from_utf8_lossy(&class.name))
+            }
+            Object::Instance(instance) => {
+                let name = &instance.as_ref().borrow().class.name;
+                write!(f, "<{} instance>", String::from_utf8_lossy(name))
+            }
+            Object::List(elements) => {
+                let pointer = Rc::as_ptr(elements) as *const ();
+                if printing.contains(&pointer) {
+                    return write!(f, "[...]");
+                }
+                printing.push(pointer);
+                write!(f, "[")?;
+                for (index, element) in elements.as_ref().borrow().iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, element, printing)?;
+                }
+                printing.pop();
+                write!(f, "]")
+            }
+            Object::Map(map) => {
+                let pointer = Rc::as_ptr(map) as *const ();
+                if printing.contains(&pointer) {
+                    return write!(f, "{{...}}");
+                }
+                printing.push(pointer);
+                write!(f, "{{")?;
+                for (index, (key, value)) in map.as_ref().borrow().entries().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, key, printing)?;
+                    write!(f, ": ")?;
+                    write_element(f, value, printing)?;
+                }
+                printing.pop();
+                write!(f, "}}")
+            }
+            Object::EnumConstructor(variant) => {
+                write!(
+                    f,
+                    "<constructor {}>",
+                    String::from_utf8_lossy(&variant.name)
+                )
+            }
+            Object::EnumValue(value) => {
+                write!(f, "{}", String::from_utf8_lossy(&value.variant.name))?;
+                if value.payload.is_empty() {
+                    return Ok(());
+                }
+                write!(f, "(")?;
+                for (index, element) in value.payload.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, element, printing)?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
+/// An element of a list, map or enum value, where strings are quoted so that `["1"]` and `[1]`
+/// print differently.
+fn write_element(
+    f: &mut std::fmt::Formatter<'_>,
+    element: &Object,
+    printing: &mut Vec<*const ()>,
+) -> std::fmt::Result {
+    let Object::String(bytes) = element else {
+        return element.write(f, printing);
+    };
+    write!(f, "\"")?;
+    for ch in String::from_utf8_lossy(bytes).chars() {
+        match ch {
+            '"' | '\\' => write!(f, "\\{ch}")?,
+            ch => write!(f, "{ch}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
 type Env = Rc<RefCell<Environment>>;
 
+/// The evaluated positional and named arguments of a call.
+type Arguments<'a> = (Vec<Object>, Vec<(&'a Token, Object)>);
+
 #[derive(Default, Debug)]
 pub(crate) struct Environment {
-    values: HashMap<Bytes, Object>,
+    values: SymbolMap<Object>,
     parent_env: Option<Env>,
 }
 
 impl Environment {
     pub(crate) fn with_parent(parent: Env) -> Self {
         Self {
-            values: HashMap::default(),
+            values: SymbolMap::default(),
             parent_env: Some(parent),
         }
     }
-    pub(crate) fn add(&mut self, key: Bytes, val: Object) -> Option<Object> {
+    pub(crate) fn add(&mut self, key: Symbol, val: Object) -> Option<Object> {
         self.values.insert(key, val)
     }
 
-    pub(crate) fn assign(&mut self, key: Bytes, val: Object) -> Option<Object> {
-        if self.values.contains_key(key.as_ref()) {
-            return self.values.insert(key, val);
+    pub(crate) fn assign(&mut self, key: Symbol, val: Object) -> Option<Object> {
+        if let Some(value) = self.values.get_mut(&key) {
+            return Some(std::mem::replace(value, val));
         }
         if let Some(parent_env) = self.parent_env.as_ref() {
             return parent_env.clone().as_ref().borrow_mut().assign(key, val);
@@ -108,21 +277,19 @@ impl Environment {
         unreachable!()
     }
 
-    pub(crate) fn get<K: AsRef<[u8]>>(&self, key: K) -> Object {
-        if self.values.contains_key(key.as_ref()) {
-            return self
-                .values
-                .get(key.as_ref())
-                .map_or(Object::Nil, |v| v.clone());
+    /// The value of the variable, `None` if it isn't declared in this or any parent environment.
+    pub(crate) fn get(&self, key: Symbol) -> Option<Object> {
+        if let Some(value) = self.values.get(&key) {
+            return Some(value.clone());
         }
         if let Some(parent_env) = &self.parent_env {
-            return parent_env.as_ref().borrow().get(key.as_ref());
+            return parent_env.as_ref().borrow().get(key);
         }
-        Object::Nil
+        None
     }
 
-    pub(crate) fn is_declared<K: AsRef<[u8]>>(&self, key: K) -> bool {
-        if self.values.contains_key(key.as_ref()) {
+    pub(crate) fn is_declared(&self, key: Symbol) -> bool {
+        if self.values.contains_key(&key) {
             return true;
         }
         if let Some(parent_env) = &self.parent_env {
@@ -138,23 +305,24 @@ where
 {
     writer: W,
     parser: Parser,
+    this_symbol: Symbol,
+    init_symbol: Symbol,
 }
 
 pub(crate) enum EvaluationError {
     ParseError(ParseError),
-    ExpectedSomethingButGotOther {
-        expected: &'static str,
-        got: Object,
-    },
+    ExpectedSomethingButGotOther { expected: &'static str, got: Object },
     Runtime(String),
-    InvalidOperation {
-        left: Object,
-        operator: Token,
-        right: Object,
-    },
-    UndefinedVariable {
-        identifier: Bytes,
-    },
+    // NOTE: boxed, the operands and the operator would make every `Result` as large as them.
+    InvalidOperation(Box<InvalidOperation>),
+    UndefinedVariable { identifier: Bytes, span: Span },
+}
+
+pub(crate) struct InvalidOperation {
+    left: Object,
+    operator: Token,
+    right: Object,
+    span: Span,
 }
 
 impl std::fmt::Debug for EvaluationError {
@@ -164,18 +332,23 @@ impl std::fmt::Debug for EvaluationError {
             EvaluationError::ExpectedSomethingButGotOther { expected, got } => {
                 write!(f, "expected: {expected}, but got: {got}")
             }
-            EvaluationError::InvalidOperation {
-                left,
-                operator,
-                right,
-            } => write!(
-                f,
-                "InvalidOperation: {operator}, left: {left}, right: {right}"
-            ),
+            EvaluationError::InvalidOperation(operation) => {
+                let InvalidOperation {
+                    left,
+                    operator,
+                    right,
+                    span,
+                } = operation.as_ref();
+                write!(
+                    f,
+                    "InvalidOperation: {operator}, left: {left}, right: {right}\n[line {}]",
+                    span.line
+                )
+            }
             EvaluationError::Runtime(str) => write!(f, "runtime error: {str}"),
-            EvaluationError::UndefinedVariable { identifier } => {
+            EvaluationError::UndefinedVariable { identifier, span } => {
                 let ident = unsafe { std::str::from_utf8_unchecked(identifier) };
-                write!(f, "undefined variable '{ident}'")
+                write!(f, "undefined variable '{ident}'\n[line {}]", span.line)
             }
         }
     }
@@ -184,6 +357,7 @@ fn evaluate_string_infix_operation(
     operator: Token,
     left: &Bytes,
     right: &Bytes,
+    span: Span,
 ) -> Result<Object, EvaluationError> {
     match operator.clone() {
         Token::PLUS => {
@@ -203,17 +377,44 @@ fn evaluate_string_infix_operation(
             let right = unsafe { std::str::from_utf8_unchecked(right.as_ref()) };
             Ok(Object::Boolean(left != right))
         }
-        token => Err(EvaluationError::InvalidOperation {
-            left: Object::String(left.clone()),
-            operator,
-            right: Object::String(right.clone()),
-        }),
+        token => Err(EvaluationError::InvalidOperation(Box::new(
+            InvalidOperation {
+                left: Object::String(left.clone()),
+                operator,
+                right: Object::String(right.clone()),
+                span,
+            },
+        ))),
     }
 }
-fn evaluate_numeric_infix_operation(operator: Token, left_value: f64, right_value: f64) -> Object {
-    match operator {
+/// `%` and `~/` round the quotient towards negative infinity, so the remainder has the sign of
+/// the divisor and `a == (a ~/ b) * b + a % b`. Both are an error when dividing by zero, while
+/// `/` follows IEEE 754 and gives an infinity or NaN.
+fn evaluate_numeric_infix_operation(
+    operator: Token,
+    left_value: f64,
+    right_value: f64,
+    span: Span,
+) -> Result<Object, EvaluationError> {
+    if matches!(operator, Token::PERCENT | Token::TILDESLASH) && right_value == 0.0 {
+        return Err(EvaluationError::Runtime(format!(
+            "Division by zero.\n[line {}]",
+            span.line
+        )));
+    }
+    let object = match operator {
         Token::STAR => Object::Number(left_value * right_value),
         Token::SLASH => Object::Number(left_value / right_value),
+        Token::STARSTAR => Object::Number(left_value.powf(right_value)),
+        Token::TILDESLASH => Object::Number((left_value / right_value).floor()),
+        Token::PERCENT => {
+            let remainder = left_value % right_value;
+            if remainder != 0.0 && (remainder < 0.0) != (right_value < 0.0) {
+                Object::Number(remainder + right_value)
+            } else {
+                Object::Number(remainder)
+            }
+        }
         Token::PLUS => Object::Number(left_value + right_value),
         Token::MINUS => Object::Number(left_value - right_value),
         Token::EQUALEQUAL => Object::Boolean(left_value == right_value),
@@ -223,13 +424,15 @@ fn evaluate_numeric_infix_operation(operator: Token, left_value: f64, right_valu
         Token::GREATER => Object::Boolean(left_value > right_value),
         Token::GREATEREQUAL => Object::Boolean(left_value >= right_value),
         token => unimplemented!("{token}"),
-    }
+    };
+    Ok(object)
 }
 
 fn evaluate_infix_expression_for_different_types_of_operands(
     operator: Token,
     left: &Object,
     right: &Object,
+    span: Span,
 ) -> Result<Object, EvaluationError> {
     match operator {
         Token::EQUALEQUAL => match (left, right) {
@@ -239,25 +442,248 @@ fn evaluate_infix_expression_for_different_types_of_operands(
         Token::BANGEQUAL => Ok(Object::Boolean(true)),
         Token::PLUS => {
             return Err(EvaluationError::Runtime(format!(
-                "Operands must be two numbers or two strings."
+                "Operands must be two numbers or two strings.\n[line {}]",
+                span.line
             )))
         }
         Token::MINUS
         | Token::SLASH
         | Token::STAR
+        | Token::STARSTAR
+        | Token::PERCENT
+        | Token::TILDESLASH
         | Token::LESS
         | Token::LESSEQUAL
         | Token::GREATER
         | Token::GREATEREQUAL => Err(EvaluationError::Runtime(format!(
-            "Error: Operands must be numbers." // TODO: need to print the line number as well.
+            "Error: Operands must be numbers.\n[line {}]",
+            span.line
+        ))),
+        _ => Err(EvaluationError::InvalidOperation(Box::new(
+            InvalidOperation {
+                left: left.clone(),
+                operator,
+                right: right.clone(),
+                span,
+            },
+        ))),
+    }
+}
+fn evaluate_infix_operation(
+    operator: Token,
+    left_value: Object,
+    right_value: Object,
+    span: Span,
+) -> Result<Object, EvaluationError> {
+    match (&left_value, &right_value) {
+        (Object::Number(left), Object::Number(right)) => {
+            evaluate_numeric_infix_operation(operator, *left, *right, span)
+        }
+        (Object::String(left), Object::String(right)) => {
+            evaluate_string_infix_operation(operator, left, right, span)
+        }
+        // NOTE: classes and instances are equal only to themselves.
+        (Object::Class(left), Object::Class(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(Rc::ptr_eq(left, right))),
+            Token::BANGEQUAL => Ok(Object::Boolean(!Rc::ptr_eq(left, right))),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value.clone(),
+                    operator: operator.clone(),
+                    right: right_value.clone(),
+                    span,
+                },
+            ))),
+        },
+        (Object::Instance(left), Object::Instance(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(Rc::ptr_eq(left, right))),
+            Token::BANGEQUAL => Ok(Object::Boolean(!Rc::ptr_eq(left, right))),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value.clone(),
+                    operator: operator.clone(),
+                    right: right_value.clone(),
+                    span,
+                },
+            ))),
+        },
+        // NOTE: so are lists, two lists with the same elements are still different lists.
+        (Object::List(left), Object::List(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(Rc::ptr_eq(left, right))),
+            Token::BANGEQUAL => Ok(Object::Boolean(!Rc::ptr_eq(left, right))),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value.clone(),
+                    operator: operator.clone(),
+                    right: right_value.clone(),
+                    span,
+                },
+            ))),
+        },
+        (Object::Map(left), Object::Map(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(Rc::ptr_eq(left, right))),
+            Token::BANGEQUAL => Ok(Object::Boolean(!Rc::ptr_eq(left, right))),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value.clone(),
+                    operator: operator.clone(),
+                    right: right_value.clone(),
+                    span,
+                },
+            ))),
+        },
+        (Object::EnumConstructor(left), Object::EnumConstructor(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(Rc::ptr_eq(left, right))),
+            Token::BANGEQUAL => Ok(Object::Boolean(!Rc::ptr_eq(left, right))),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value.clone(),
+                    operator: operator.clone(),
+                    right: right_value.clone(),
+                    span,
+                },
+            ))),
+        },
+        // NOTE: unlike lists, enum values are equal when they are the same variant with equal
+        // payloads.
+        (Object::EnumValue(left), Object::EnumValue(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(enum_values_equal(left, right, span)?)),
+            Token::BANGEQUAL => Ok(Object::Boolean(!enum_values_equal(left, right, span)?)),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value.clone(),
+                    operator: operator.clone(),
+                    right: right_value.clone(),
+                    span,
+                },
+            ))),
+        },
+        (Object::Boolean(left), Object::Boolean(right)) => match operator.clone() {
+            Token::EQUALEQUAL => Ok(Object::Boolean(*left == *right)),
+            Token::BANGEQUAL => Ok(Object::Boolean(*left != *right)),
+            token => Err(EvaluationError::InvalidOperation(Box::new(
+                InvalidOperation {
+                    left: left_value,
+                    operator: operator.clone(),
+                    right: right_value,
+                    span,
+                },
+            ))),
+        },
+        _ => evaluate_infix_expression_for_different_types_of_operands(
+            operator,
+            &left_value,
+            &right_value,
+            span,
+        ),
+    }
+}
+
+fn enum_values_equal(
+    left: &EnumValue,
+    right: &EnumValue,
+    span: Span,
+) -> Result<bool, EvaluationError> {
+    if !Rc::ptr_eq(&left.variant, &right.variant) {
+        return Ok(false);
+    }
+    for (left, right) in left.payload.iter().zip(right.payload.iter()) {
+        let is_equal =
+            evaluate_infix_operation(Token::EQUALEQUAL, left.clone(), right.clone(), span)?;
+        if !is_equal.get_truthy_value() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The position in a list of `len` elements that `index` refers to.
+fn list_index(index: &Object, len: usize, span: Span) -> Result<usize, EvaluationError> {
+    let Object::Number(index) = *index else {
+        return Err(EvaluationError::Runtime(format!(
+            "Index must be a number.\n[line {}]",
+            span.line
+        )));
+    };
+    if index.fract() != 0.0 {
+        return Err(EvaluationError::Runtime(format!(
+            "Index must be an integer, got {index}.\n[line {}]",
+            span.line
+        )));
+    }
+    if index < 0.0 {
+        return Err(EvaluationError::Runtime(format!(
+            "Index {index} is negative.\n[line {}]",
+            span.line
+        )));
+    }
+    if index >= len as f64 {
+        return Err(EvaluationError::Runtime(format!(
+            "Index {index} is out of range for a list of length {len}.\n[line {}]",
+            span.line
+        )));
+    }
+    Ok(index as usize)
+}
+
+fn map_key(key: &Object, span: Span) -> Result<MapKey, EvaluationError> {
+    MapKey::new(key).ok_or_else(|| {
+        EvaluationError::Runtime(format!(
+            "Map keys must be numbers, strings, booleans or nil, got {key}.\n[line {}]",
+            span.line
+        ))
+    })
+}
+
+/// `container[index]`. A key that isn't in a map gives `nil`.
+fn get_index(container: Object, index: &Object, span: Span) -> Result<Object, EvaluationError> {
+    match container {
+        Object::List(list) => {
+            let elements = list.as_ref().borrow();
+            Ok(elements[list_index(index, elements.len(), span)?].clone())
+        }
+        Object::Map(map) => {
+            let key = map_key(index, span)?;
+            Ok(map
+                .as_ref()
+                .borrow()
+                .get(&key)
+                .cloned()
+                .unwrap_or(Object::Nil))
+        }
+        _ => Err(EvaluationError::Runtime(format!(
+            "Only lists and maps can be indexed.\n[line {}]",
+            span.line
+        ))),
+    }
+}
+
+/// `container[index] = value`. Lists can only be assigned within their length, maps take new keys.
+fn set_index(
+    container: Object,
+    index: Object,
+    value: Object,
+    span: Span,
+) -> Result<(), EvaluationError> {
+    match container {
+        Object::List(list) => {
+            let mut elements = list.as_ref().borrow_mut();
+            let position = list_index(&index, elements.len(), span)?;
+            elements[position] = value;
+            Ok(())
+        }
+        Object::Map(map) => {
+            let key = map_key(&index, span)?;
+            map.as_ref().borrow_mut().insert(key, index, value);
+            Ok(())
+        }
+        _ => Err(EvaluationError::Runtime(format!(
+            "Only lists and maps can be indexed.\n[line {}]",
+            span.line
         ))),
-        _ => Err(EvaluationError::InvalidOperation {
-            left: left.clone(),
-            operator: operator,
-            right: right.clone(),
-        }),
     }
 }
+
 impl<W> Interpreter<W>
 where
     W: Write,
@@ -265,11 +691,25 @@ where
     pub(crate) fn from_source(source: String, writer: W) -> Result<Self, ParseError> {
         let parser = Parser::from_source(source)?;
 
-        Ok(Self {
+        Ok(Self::new(parser, writer))
+    }
+
+    pub(crate) fn from_reader<R: Read + 'static>(reader: R, writer: W) -> Result<Self, ParseError> {
+        let parser = Parser::from_reader(reader)?;
+
+        Ok(Self::new(parser, writer))
+    }
+
+    fn new(parser: Parser, writer: W) -> Self {
+        let interner = parser.interner();
+        let mut interner = interner.borrow_mut();
+        Self {
+            this_symbol: interner.intern(b"this"),
+            init_symbol: interner.intern(b"init"),
             writer,
             parser,
             // global_env: Environment::default(),
-        })
+        }
     }
 
     fn evaluate_and_expression(
@@ -299,41 +739,151 @@ where
         }
     }
 
+    /// Evaluates the object and index of an assignment target, so that a compound assignment
+    /// reads and writes the target without evaluating them twice.
+    fn evaluate_place<'a>(
+        &mut self,
+        target: &'a Expression,
+        env: Env,
+    ) -> Result<Place<'a>, EvaluationError> {
+        match &target.kind {
+            ExpressionKind::Ident(symbol, name) => Ok(Place::Variable {
+                symbol: *symbol,
+                name,
+            }),
+            ExpressionKind::Get { object, property } => {
+                let Object::Instance(instance) = self.evaluate_expression(object, env)? else {
+                    return Err(EvaluationError::Runtime(format!(
+                        "Only instances have fields.\n[line {}]",
+                        target.span.line
+                    )));
+                };
+                Ok(Place::Field { instance, property })
+            }
+            ExpressionKind::Index { object, index } => {
+                let container = self.evaluate_expression(object, env.clone())?;
+                let index = self.evaluate_expression(index, env)?;
+                Ok(Place::Index { container, index })
+            }
+            expr => Err(EvaluationError::Runtime(format!(
+                "expected expression but got {expr:?}\n[line {}]",
+                target.span.line
+            ))),
+        }
+    }
+
+    fn read_place(&self, place: &Place, span: Span, env: &Env) -> Result<Object, EvaluationError> {
+        match place {
+            Place::Variable { symbol, name } => {
+                env.as_ref().borrow().get(*symbol).ok_or_else(|| {
+                    EvaluationError::UndefinedVariable {
+                        identifier: (*name).clone(),
+                        span,
+                    }
+                })
+            }
+            Place::Field { instance, property } => {
+                self.get_property(instance.clone(), property, span)
+            }
+            Place::Index { container, index } => get_index(container.clone(), index, span),
+        }
+    }
+
+    fn write_place(
+        &self,
+        place: Place,
+        value: Object,
+        span: Span,
+        env: &Env,
+    ) -> Result<(), EvaluationError> {
+        match place {
+            Place::Variable { symbol, name } => {
+                if !env.as_ref().borrow().is_declared(symbol) {
+                    return Err(EvaluationError::UndefinedVariable {
+                        identifier: name.clone(),
+                        span,
+                    });
+                }
+                env.as_ref().borrow_mut().assign(symbol, value);
+            }
+            Place::Field { instance, property } => {
+                let symbol = property.get_symbol().unwrap(); // NOTE: the parser only allows identifiers.
+                instance.as_ref().borrow_mut().fields.insert(symbol, value);
+            }
+            Place::Index { container, index } => set_index(container, index, value, span)?,
+        }
+        Ok(())
+    }
+
+    /// `=` and the compound assignments, `x += y` is `x = x + y` with `x` evaluated once.
     fn evaluate_assignment_infix_expression(
         &mut self,
+        operator: Token,
         left_expr: &Expression,
         right_expr: &Expression,
+        span: Span,
         env: Env,
     ) -> Result<Object, EvaluationError> {
-        let ident_bytes = match left_expr {
-            Expression::Ident(ident_bytes) => ident_bytes,
-            expr => {
-                return Err(EvaluationError::Runtime(format!(
-                    "expected expression but got {expr:?}"
-                )))
+        let place = self.evaluate_place(left_expr, env.clone())?;
+        let value = match operator {
+            Token::EQUAL => self.evaluate_expression(right_expr, env.clone())?,
+            operator => {
+                let current = self.read_place(&place, left_expr.span, &env)?;
+                let right_value = self.evaluate_expression(right_expr, env.clone())?;
+                let operator = match operator {
+                    Token::PLUSEQUAL => Token::PLUS,
+                    Token::MINUSEQUAL => Token::MINUS,
+                    Token::STAREQUAL => Token::STAR,
+                    Token::SLASHEQUAL => Token::SLASH,
+                    t => unreachable!("token: {}", t),
+                };
+                evaluate_infix_operation(operator, current, right_value, span)?
             }
         };
-        let value = self.evaluate_expression(right_expr, env.clone())?;
-        if !env.as_ref().borrow().is_declared(ident_bytes.as_ref()) {
-            return Err(EvaluationError::UndefinedVariable {
-                identifier: ident_bytes.clone(),
-            });
-        }
-        env.as_ref()
-            .borrow_mut()
-            .assign(ident_bytes.clone(), value.clone());
+        self.write_place(place, value.clone(), left_expr.span, &env)?;
         Ok(value)
     }
 
+    /// `++` and `--`, prefix ones give the updated value and postfix ones the previous value.
+    fn evaluate_update_expression(
+        &mut self,
+        operator: &Token,
+        target: &Expression,
+        is_prefix: bool,
+        span: Span,
+        env: Env,
+    ) -> Result<Object, EvaluationError> {
+        let place = self.evaluate_place(target, env.clone())?;
+        let Object::Number(previous) = self.read_place(&place, target.span, &env)? else {
+            return Err(EvaluationError::Runtime(format!(
+                "Error: Operand of '{operator}' must be a number.\n[line {}]",
+                span.line
+            )));
+        };
+        let updated = match operator {
+            Token::PLUSPLUS => previous + 1.0,
+            _ => previous - 1.0,
+        };
+        self.write_place(place, Object::Number(updated), target.span, &env)?;
+        Ok(Object::Number(if is_prefix { updated } else { previous }))
+    }
+
     fn evaluate_infix_expression(
         &mut self,
         operator: Token,
         left_expr: &Expression,
         right_expr: &Expression,
+        span: Span,
         env: Rc<RefCell<Environment>>,
     ) -> Result<Object, EvaluationError> {
-        if let Token::EQUAL = operator {
-            return self.evaluate_assignment_infix_expression(left_expr, right_expr, env);
+        if let Token::EQUAL
+        | Token::PLUSEQUAL
+        | Token::MINUSEQUAL
+        | Token::STAREQUAL
+        | Token::SLASHEQUAL = operator
+        {
+            return self
+                .evaluate_assignment_infix_expression(operator, left_expr, right_expr, span, env);
         }
         if let Token::And = operator {
             return self.evaluate_and_expression(left_expr, right_expr, env);
@@ -343,34 +893,14 @@ where
         }
         let left_value = self.evaluate_expression(left_expr, env.clone())?;
         let right_value = self.evaluate_expression(right_expr, env.clone())?;
-        match (&left_value, &right_value) {
-            (Object::Number(left), Object::Number(right)) => {
-                Ok(evaluate_numeric_infix_operation(operator, *left, *right))
-            }
-            (Object::String(left), Object::String(right)) => {
-                evaluate_string_infix_operation(operator, left, right)
-            }
-            (Object::Boolean(left), Object::Boolean(right)) => match operator.clone() {
-                Token::EQUALEQUAL => Ok(Object::Boolean(*left == *right)),
-                Token::BANGEQUAL => Ok(Object::Boolean(*left != *right)),
-                token => Err(EvaluationError::InvalidOperation {
-                    left: left_value,
-                    operator: operator.clone(),
-                    right: right_value,
-                }),
-            },
-            _ => evaluate_infix_expression_for_different_types_of_operands(
-                operator,
-                &left_value,
-                &right_value,
-            ),
-        }
+        evaluate_infix_operation(operator, left_value, right_value, span)
     }
 
     fn evaluate_prefix_expression(
         &mut self,
         operator: Token,
         expression: &Expression,
+        span: Span,
         env: Rc<RefCell<Environment>>,
     ) -> Result<Object, EvaluationError> {
         let value = self.evaluate_expression(expression, env)?;
@@ -381,7 +911,7 @@ where
                 object => {
                     return Err(EvaluationError::Runtime(format!(
                         "Error: Operand must be a number.\n[line {}]",
-                        self.parser.get_curr_line()
+                        span.line
                     )))
                 }
             },
@@ -395,24 +925,48 @@ where
         expression: &Expression,
         env: Env,
     ) -> Result<Object, EvaluationError> {
-        let val = match expression {
-            Expression::NilLiteral => Object::Nil,
-            Expression::Ident(ident_bytes) => {
-                if !env.as_ref().borrow().is_declared(ident_bytes) {
+        let val = match &expression.kind {
+            ExpressionKind::NilLiteral => Object::Nil,
+            ExpressionKind::Ident(symbol, ident_bytes) => {
+                let Some(value) = env.as_ref().borrow().get(*symbol) else {
                     return Err(EvaluationError::UndefinedVariable {
                         identifier: ident_bytes.clone(),
+                        span: expression.span,
                     });
+                };
+                value
+            }
+            ExpressionKind::BooleanLiteral(v) => Object::Boolean(*v),
+            ExpressionKind::NumberLiteral(v) => Object::Number(*v),
+            ExpressionKind::StringLiteral(bytes) => Object::String(bytes.clone()),
+            ExpressionKind::InterpolatedString(parts) => {
+                let mut buf = BytesMut::new();
+                for part in parts.iter() {
+                    match self.evaluate_expression(part, env.clone())? {
+                        Object::String(bytes) => buf.put(bytes.as_ref()),
+                        object => buf.put(object.to_string().as_bytes()),
+                    }
                 }
-                env.as_ref().borrow().get(ident_bytes)
+                Object::String(buf.freeze())
+            }
+            ExpressionKind::GroupedExpression(expr) => {
+                self.evaluate_expression(expr.as_ref(), env)?
             }
-            Expression::BooleanLiteral(v) => Object::Boolean(*v),
-            Expression::NumberLiteral(v) => Object::Number(*v),
-            Expression::StringLiteral(bytes) => Object::String(bytes.clone()),
-            Expression::GroupedExpression(expr) => self.evaluate_expression(expr.as_ref(), env)?,
-            Expression::PrefixExpression { operator, expr } => {
-                self.evaluate_prefix_expression(operator.clone(), expr.as_ref(), env)?
+            ExpressionKind::PrefixExpression {
+                operator: operator @ (Token::PLUSPLUS | Token::MINUSMINUS),
+                expr,
+            } => self.evaluate_update_expression(operator, expr, true, expression.span, env)?,
+            ExpressionKind::PostfixExpression { operator, expr } => {
+                self.evaluate_update_expression(operator, expr, false, expression.span, env)?
             }
-            Expression::InfixExpression {
+            ExpressionKind::PrefixExpression { operator, expr } => self
+                .evaluate_prefix_expression(
+                    operator.clone(),
+                    expr.as_ref(),
+                    expression.span,
+                    env,
+                )?,
+            ExpressionKind::InfixExpression {
                 operator,
                 left_expr,
                 right_expr,
@@ -420,17 +974,75 @@ where
                 operator.clone(),
                 left_expr.as_ref(),
                 right_expr.as_ref(),
+                expression.span,
                 env,
             )?,
-            Expression::Print(e) => {
+            ExpressionKind::Print(e) => {
                 let val = self.evaluate_expression(e.as_ref(), env.clone())?;
                 let _ = writeln!(self.writer, "{}", val);
                 Object::Nil
             }
-            Expression::Function(fe) => {
+            ExpressionKind::Function(fe) => {
                 self.evaluate_funtion_expression(fe.clone(), env.clone())?
             }
-            Expression::Call(ce) => self.evaluate_function_call(ce, env.clone())?,
+            ExpressionKind::Call(ce) => {
+                self.evaluate_function_call(ce, expression.span, env.clone())?
+            }
+            ExpressionKind::Get { object, property } => {
+                let Object::Instance(instance) = self.evaluate_expression(object, env.clone())?
+                else {
+                    return Err(EvaluationError::Runtime(format!(
+                        "Only instances have properties.\n[line {}]",
+                        expression.span.line
+                    )));
+                };
+                self.get_property(instance, property, expression.span)?
+            }
+            ExpressionKind::This(symbol) => {
+                let Some(instance) = env.as_ref().borrow().get(*symbol) else {
+                    return Err(EvaluationError::Runtime(format!(
+                        "Can't use 'this' outside of a class.\n[line {}]",
+                        expression.span.line
+                    )));
+                };
+                instance
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                // NOTE: only the chosen branch is evaluated.
+                if self
+                    .evaluate_expression(condition, env.clone())?
+                    .get_truthy_value()
+                {
+                    self.evaluate_expression(then_branch, env)?
+                } else {
+                    self.evaluate_expression(else_branch, env)?
+                }
+            }
+            ExpressionKind::List(elements) => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements.iter() {
+                    values.push(self.evaluate_expression(element, env.clone())?);
+                }
+                Object::List(Rc::new(RefCell::new(values)))
+            }
+            ExpressionKind::Map(entries) => {
+                let mut map = Map::default();
+                for (key, value) in entries.iter() {
+                    let key_object = self.evaluate_expression(key, env.clone())?;
+                    let value = self.evaluate_expression(value, env.clone())?;
+                    map.insert(map_key(&key_object, key.span)?, key_object, value);
+                }
+                Object::Map(Rc::new(RefCell::new(map)))
+            }
+            ExpressionKind::Index { object, index } => {
+                let container = self.evaluate_expression(object, env.clone())?;
+                let index = self.evaluate_expression(index, env)?;
+                get_index(container, &index, expression.span)?
+            }
         };
         Ok(val)
     }
@@ -442,63 +1054,272 @@ where
         Ok((func.as_ref())(None))
     }
 
+    /// A field of the instance, or else one of its class's methods bound to it.
+    fn get_property(
+        &self,
+        instance: Rc<RefCell<Instance>>,
+        property: &Token,
+        span: Span,
+    ) -> Result<Object, EvaluationError> {
+        let symbol = property.get_symbol().unwrap(); // NOTE: the parser only allows identifiers.
+        if let Some(value) = instance.as_ref().borrow().fields.get(&symbol) {
+            return Ok(value.clone());
+        }
+        let class = instance.as_ref().borrow().class.clone();
+        match class.methods.get(&symbol) {
+            Some(method) => Ok(Object::Function(self.bind_method(method, &class, instance))),
+            None => Err(EvaluationError::Runtime(format!(
+                "Undefined property '{property}'.\n[line {}]",
+                span.line
+            ))),
+        }
+    }
+
+    /// The method as a function whose environment has `this` bound to the instance.
+    fn bind_method(
+        &self,
+        method: &Rc<FunctionExpression>,
+        class: &Class,
+        instance: Rc<RefCell<Instance>>,
+    ) -> Function {
+        let mut env = Environment::with_parent(class.env.clone());
+        env.add(self.this_symbol, Object::Instance(instance));
+        let is_initializer = method
+            .name
+            .as_ref()
+            .and_then(Token::get_symbol)
+            .is_some_and(|name| name == self.init_symbol);
+        Function {
+            fe: method.clone(),
+            env: Rc::new(RefCell::new(env)),
+            is_initializer,
+        }
+    }
+
     fn evaluate_function_call(
         &mut self,
         call_expr: &CallExpression,
+        span: Span,
         env: Env,
     ) -> Result<Object, EvaluationError> {
-        let Function {
-            fe: func_expr,
-            env: captured_env,
-        } = match self.evaluate_expression(call_expr.callee.as_ref(), env.clone())? {
-            Object::Function(fe) => fe,
+        let function = match self.evaluate_expression(call_expr.callee.as_ref(), env.clone())? {
+            Object::Function(function) => function,
             Object::NativeFunction(nfe) => return self.evaluate_native_function_call(nfe),
+            Object::Class(class) => return self.instantiate_class(class, call_expr, span, env),
+            Object::EnumConstructor(variant) => {
+                return self.construct_enum_value(variant, call_expr, span, env)
+            }
             expr => {
                 return Err(EvaluationError::Runtime(format!(
-                    "Callee must be a function"
+                    "Callee must be a function.\n[line {}]",
+                    span.line
                 )))
             }
         };
-        let arguments_count = call_expr
-            .arguments
-            .as_ref()
-            .and_then(|args| Some(args.len()))
-            .unwrap_or_else(|| 0);
+        self.call_function(function, call_expr, span, env)
+    }
 
-        let mut parameter_count = func_expr
-            .as_ref()
-            .parameters
-            .as_ref()
-            .and_then(|args| Some(args.len()))
-            .unwrap_or_else(|| 0);
+    /// Calling a class creates an instance, which is initialized by the `init` method if the
+    /// class has one.
+    fn instantiate_class(
+        &mut self,
+        class: Rc<Class>,
+        call_expr: &CallExpression,
+        span: Span,
+        env: Env,
+    ) -> Result<Object, EvaluationError> {
+        let instance = Rc::new(RefCell::new(Instance {
+            class: class.clone(),
+            fields: SymbolMap::default(),
+        }));
+        match class.methods.get(&self.init_symbol) {
+            Some(init) => {
+                let initializer = self.bind_method(init, &class, instance.clone());
+                self.call_function(initializer, call_expr, span, env)?;
+            }
+            None => {
+                let arguments_count = call_expr.arguments.as_ref().map_or(0, |args| args.len());
+                if arguments_count != 0 {
+                    return Err(EvaluationError::Runtime(format!(
+                        "Expected 0 arguments but got {arguments_count}.\n[line {}]",
+                        span.line
+                    )));
+                }
+            }
+        }
+        Ok(Object::Instance(instance))
+    }
 
-        if arguments_count != parameter_count {
+    /// Calling a variant with a payload creates a value of that variant, its payload given by
+    /// position.
+    fn construct_enum_value(
+        &mut self,
+        variant: Rc<Variant>,
+        call_expr: &CallExpression,
+        span: Span,
+        env: Env,
+    ) -> Result<Object, EvaluationError> {
+        let (payload, named) = self.evaluate_arguments(call_expr, env)?;
+        if let Some((name, _)) = named.first() {
             return Err(EvaluationError::Runtime(format!(
-                "Expected {parameter_count} arguments but got {arguments_count}."
+                "Unknown argument '{name}'.\n[line {}]",
+                span.line
             )));
         }
-        let child_env = Rc::new(RefCell::new(Environment::with_parent(captured_env.clone())));
-        if arguments_count != 0 {
-            let mut parameters = func_expr.parameters.as_ref().unwrap().iter();
-            let mut arguments = call_expr.arguments.as_ref().unwrap().iter();
-            while parameter_count > 0 {
-                let parameter = parameters.next().unwrap();
-                let argument = arguments.next().unwrap();
-                let arg_val = self.evaluate_expression(argument, env.clone())?;
-                let name_bytes = parameter.get_bytes().unwrap(); // NOTE: ideally this should never fail.
-                child_env
-                    .as_ref()
-                    .borrow_mut()
-                    .add(name_bytes.clone(), arg_val);
+        if payload.len() != variant.arity {
+            return Err(EvaluationError::Runtime(format!(
+                "Expected {} arguments but got {}.\n[line {}]",
+                variant.arity,
+                payload.len(),
+                span.line
+            )));
+        }
+        Ok(Object::EnumValue(Rc::new(EnumValue { variant, payload })))
+    }
 
-                parameter_count -= 1;
+    /// Evaluates the arguments of a call in order, expanding spread lists in place, into the
+    /// positional and the named ones.
+    fn evaluate_arguments<'a>(
+        &mut self,
+        call_expr: &'a CallExpression,
+        env: Env,
+    ) -> Result<Arguments<'a>, EvaluationError> {
+        let arguments = call_expr.arguments.as_deref().unwrap_or_default();
+        let mut positional: Vec<Object> = vec![];
+        let mut named: Vec<(&Token, Object)> = vec![];
+        for argument in arguments.iter() {
+            let value = self.evaluate_expression(&argument.value, env.clone())?;
+            match (&argument.name, value) {
+                (Some(name), value) => named.push((name, value)),
+                (None, Object::List(list)) if argument.is_spread => {
+                    positional.extend(list.as_ref().borrow().iter().cloned())
+                }
+                (None, value) if argument.is_spread => {
+                    return Err(EvaluationError::Runtime(format!(
+                        "Only lists can be spread, got {value}.\n[line {}]",
+                        argument.value.span.line
+                    )))
+                }
+                (None, value) => positional.push(value),
             }
         }
+        Ok((positional, named))
+    }
+
+    /// Binds the arguments of a call to the parameters of the function in `call_env`: positional
+    /// arguments in order, with spread lists expanded in place, named ones by name, and the
+    /// defaults of the parameters left out. A rest parameter collects the extra positional
+    /// arguments into a list. The arguments are all evaluated before they are checked against the
+    /// parameters.
+    fn bind_arguments(
+        &mut self,
+        func_expr: &FunctionExpression,
+        call_expr: &CallExpression,
+        span: Span,
+        env: Env,
+        call_env: &Env,
+    ) -> Result<(), EvaluationError> {
+        let parameters = func_expr.parameters.as_deref().unwrap_or_default();
+        let (parameters, rest) = match parameters.split_last() {
+            Some((last, parameters)) if last.is_rest => (parameters, Some(last)),
+            _ => (parameters, None),
+        };
+
+        let (mut positional, named) = self.evaluate_arguments(call_expr, env)?;
+        // NOTE: with neither defaults, named arguments nor a rest parameter, a call just has the
+        // wrong number of arguments.
+        let is_positional_only = named.is_empty()
+            && rest.is_none()
+            && parameters.iter().all(|param| param.default.is_none());
+        if (positional.len() > parameters.len() && rest.is_none())
+            || (is_positional_only && positional.len() != parameters.len())
+        {
+            return Err(EvaluationError::Runtime(format!(
+                "Expected {} arguments but got {}.\n[line {}]",
+                parameters.len(),
+                positional.len(),
+                span.line
+            )));
+        }
+
+        let extra = positional.split_off(positional.len().min(parameters.len()));
+        let mut values: Vec<Option<Object>> = positional.into_iter().map(Some).collect();
+        values.resize(parameters.len(), None);
+        for (name, value) in named {
+            let Some(position) = parameters
+                .iter()
+                .position(|param| param.name.get_symbol() == name.get_symbol())
+            else {
+                return Err(EvaluationError::Runtime(format!(
+                    "Unknown argument '{name}'.\n[line {}]",
+                    span.line
+                )));
+            };
+            if values[position].is_some() {
+                return Err(EvaluationError::Runtime(format!(
+                    "Argument '{name}' is given more than once.\n[line {}]",
+                    span.line
+                )));
+            }
+            values[position] = Some(value);
+        }
+        if let Some((missing, _)) = parameters
+            .iter()
+            .zip(values.iter())
+            .find(|(param, value)| value.is_none() && param.default.is_none())
+        {
+            return Err(EvaluationError::Runtime(format!(
+                "Missing argument '{}'.\n[line {}]",
+                missing.name, span.line
+            )));
+        }
+
+        for (parameter, value) in parameters.iter().zip(values) {
+            let value = match (value, &parameter.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.evaluate_expression(default, call_env.clone())?,
+                (None, None) => unreachable!(), // NOTE: checked above.
+            };
+            let symbol = parameter.name.get_symbol().unwrap(); // NOTE: the parser only allows identifiers.
+            call_env.as_ref().borrow_mut().add(symbol, value);
+        }
+        if let Some(rest) = rest {
+            let symbol = rest.name.get_symbol().unwrap();
+            let extra = Object::List(Rc::new(RefCell::new(extra)));
+            call_env.as_ref().borrow_mut().add(symbol, extra);
+        }
+        Ok(())
+    }
+
+    fn call_function(
+        &mut self,
+        function: Function,
+        call_expr: &CallExpression,
+        span: Span,
+        env: Env,
+    ) -> Result<Object, EvaluationError> {
+        let Function {
+            fe: func_expr,
+            env: captured_env,
+            is_initializer,
+        } = function;
+        let child_env = Rc::new(RefCell::new(Environment::with_parent(captured_env.clone())));
+        self.bind_arguments(&func_expr, call_expr, span, env, &child_env)?;
         for (index, stmt) in func_expr.body.iter().enumerate() {
             if let Right(val) = self.evaluate_stmt(stmt, child_env.clone())? {
+                if is_initializer {
+                    break;
+                }
                 return Ok(val);
             }
         }
+        if is_initializer {
+            return Ok(captured_env
+                .as_ref()
+                .borrow()
+                .get(self.this_symbol)
+                .unwrap_or(Object::Nil));
+        }
         //TODO: add the support for return stmt and returning a value from a function also.
         //For now, the function will always return a nil.
         Ok(Object::Nil)
@@ -510,13 +1331,14 @@ where
         env: Env,
     ) -> Result<Object, EvaluationError> {
         if let Some(name_token) = fe.as_ref().name.as_ref() {
-            if let Some(name_bytes) = name_token.get_bytes() {
+            if let Some(symbol) = name_token.get_symbol() {
                 // add in the environment.
                 env.as_ref().borrow_mut().add(
-                    name_bytes.clone(),
+                    symbol,
                     Object::Function(Function {
                         fe: fe.clone(),
                         env: env.clone(),
+                        is_initializer: false,
                     }),
                 );
             }
@@ -524,6 +1346,7 @@ where
         Ok(Object::Function(Function {
             fe: fe,
             env: env.clone(),
+            is_initializer: false,
         }))
     }
 
@@ -531,7 +1354,7 @@ where
         let env = Environment::default();
         let expression = self
             .parser
-            .parse_expression(Precedence::Lowest)
+            .parse_standalone_expression()
             .or_else(|e| Err(EvaluationError::ParseError(e)))?;
 
         self.evaluate_expression(&expression, Rc::new(RefCell::new(env)))
@@ -541,57 +1364,103 @@ where
         &mut self,
         stmt: &Statement,
         env: Rc<RefCell<Environment>>,
-    ) -> Result<Either<Void, Object>, EvaluationError> {
-        match stmt {
-            Statement::Expression(e) => {
+    ) -> Result<Either<Completion, Object>, EvaluationError> {
+        match &stmt.kind {
+            StatementKind::Expression(e) => {
                 self.evaluate_expression(e, env)?;
             }
-            Statement::Print(e) => {
+            StatementKind::Print(e) => {
                 let val = self.evaluate_expression(e, env)?;
                 let _ = writeln!(self.writer, "{}", val);
             }
-            Statement::VarDeclaration(VarDeclaration { identifier, expr }) => {
+            StatementKind::VarDeclaration(VarDeclaration {
+                identifier, expr, ..
+            }) => {
                 if let Some(expr) = expr {
                     let val = self.evaluate_expression(expr, env.clone())?;
-                    env.as_ref().borrow_mut().add(identifier.clone(), val);
+                    env.as_ref().borrow_mut().add(*identifier, val);
                 } else {
-                    env.as_ref()
-                        .borrow_mut()
-                        .add(identifier.clone(), Object::Nil);
+                    env.as_ref().borrow_mut().add(*identifier, Object::Nil);
                 }
             }
-            Statement::Block(stmts) => {
-                let child_env = Rc::new(RefCell::new(Environment {
-                    values: HashMap::new(),
-                    parent_env: Some(env.clone()),
-                }));
+            StatementKind::Block(stmts) => {
+                let child_env = Rc::new(RefCell::new(Environment::with_parent(env.clone())));
                 for stmt in stmts.iter() {
-                    if let Right(val) = self.evaluate_stmt(&stmt, child_env.clone())? {
-                        return Ok(Right(val));
+                    match self.evaluate_stmt(&stmt, child_env.clone())? {
+                        Left(Completion::Normal) => (),
+                        completion => return Ok(completion),
                     }
                 }
             }
-            Statement::IfStatement(if_statement) => {
-                if let Right(val) = self.evaluate_if_statement(if_statement, env.clone())? {
-                    return Ok(Right(val));
-                }
+            StatementKind::IfStatement(if_statement) => {
+                return self.evaluate_if_statement(if_statement, env.clone());
+            }
+            StatementKind::WhileLoop(while_loop) => {
+                return self.evaluate_while_statement(while_loop, env.clone());
+            }
+            StatementKind::Match(match_statement) => {
+                return self.evaluate_match_statement(match_statement, env.clone());
+            }
+            StatementKind::Break => return Ok(Left(Completion::Break)),
+            StatementKind::Continue => return Ok(Left(Completion::Continue)),
+            StatementKind::Class(ClassDeclaration { name, methods }) => {
+                // NOTE: the parser only allows identifiers as class and method names.
+                let methods = methods
+                    .iter()
+                    .map(|method| {
+                        let name = method.name.as_ref().and_then(Token::get_symbol).unwrap();
+                        (name, method.clone())
+                    })
+                    .collect();
+                let class = Class {
+                    name: name.get_bytes().unwrap(),
+                    methods,
+                    env: env.clone(),
+                };
+                env.as_ref()
+                    .borrow_mut()
+                    .add(name.get_symbol().unwrap(), Object::Class(Rc::new(class)));
             }
-            Statement::WhileLoop(while_loop) => {
-                if let Right(val) = self.evaluate_while_statement(while_loop, env.clone())? {
-                    return Ok(Right(val));
+            StatementKind::Enum(EnumDeclaration { name, variants }) => {
+                // NOTE: each variant is declared on its own, a variant without a payload is a
+                // value and the others are constructors. Since they aren't qualified by the enum,
+                // a variant of another enum in the same scope is an error instead of replaced.
+                for variant in variants.iter() {
+                    let declared = Rc::new(Variant {
+                        name: variant.name.get_bytes().unwrap(),
+                        arity: variant.fields.len(),
+                    });
+                    let value = if variant.fields.is_empty() {
+                        Object::EnumValue(Rc::new(EnumValue {
+                            variant: declared,
+                            payload: vec![],
+                        }))
+                    } else {
+                        Object::EnumConstructor(declared)
+                    };
+                    let previous = env
+                        .as_ref()
+                        .borrow_mut()
+                        .add(variant.name.get_symbol().unwrap(), value);
+                    if let Some(Object::EnumConstructor(_) | Object::EnumValue(_)) = previous {
+                        return Err(EvaluationError::Runtime(format!(
+                            "Enum variant '{}' is already declared.\n[line {}]",
+                            variant.name, stmt.span.line
+                        )));
+                    }
                 }
             }
-            Statement::Return(exp) => {
+            StatementKind::Return(exp) => {
                 return Ok(Right(self.evaluate_expression(exp, env.clone())?));
             }
         };
-        Ok(Either::Left(Void))
+        Ok(Either::Left(Completion::Normal))
     }
     fn evaluate_while_statement(
         &mut self,
         while_loop: &WhileLoop,
         env: Env,
-    ) -> Result<Either<Void, Object>, EvaluationError> {
+    ) -> Result<Either<Completion, Object>, EvaluationError> {
         loop {
             let mut val = true;
             if let Some(expr) = &while_loop.expr {
@@ -601,31 +1470,127 @@ where
             if !val {
                 break;
             }
-            if let Right(val) = self.evaluate_stmt(while_loop.block.as_ref(), env.clone())? {
-                return Ok(Right(val));
+            match self.evaluate_stmt(while_loop.block.as_ref(), env.clone())? {
+                Left(Completion::Break) => break,
+                Left(Completion::Normal | Completion::Continue) => (),
+                Right(val) => return Ok(Right(val)),
+            }
+            // NOTE: the increment of a desugared `for` loop also runs after a `continue`.
+            if let Some(increment) = &while_loop.increment {
+                self.evaluate_expression(increment, env.clone())?;
             }
         }
 
-        Ok(Either::Left(Void))
+        Ok(Either::Left(Completion::Normal))
     }
+    /// Runs the first arm that matches, if any. The payload a variant pattern destructures is
+    /// bound in a child environment of `env`, in which the guard and the body run.
+    fn evaluate_match_statement(
+        &mut self,
+        match_statement: &MatchStatement,
+        env: Env,
+    ) -> Result<Either<Completion, Object>, EvaluationError> {
+        let subject = self.evaluate_expression(&match_statement.subject, env.clone())?;
+        let line = match_statement.subject.span.line;
+        for arm in match_statement.arms.iter() {
+            let mut bindings = None;
+            for pattern in arm.patterns.iter() {
+                bindings = self.match_pattern(pattern, &subject, line, env.clone())?;
+                if bindings.is_some() {
+                    break;
+                }
+            }
+            let Some(bindings) = bindings else {
+                continue;
+            };
+            let arm_env = Rc::new(RefCell::new(Environment::with_parent(env.clone())));
+            for (symbol, value) in bindings {
+                arm_env.as_ref().borrow_mut().add(symbol, value);
+            }
+            if let Some(guard) = &arm.guard {
+                if !self
+                    .evaluate_expression(guard, arm_env.clone())?
+                    .get_truthy_value()
+                {
+                    continue;
+                }
+            }
+            return self.evaluate_stmt(&arm.body, arm_env);
+        }
+        Ok(Left(Completion::Normal))
+    }
+
+    /// The variables `pattern` binds if `subject` matches it, `None` if it doesn't.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        subject: &Object,
+        line: u32,
+        env: Env,
+    ) -> Result<Option<Vec<(Symbol, Object)>>, EvaluationError> {
+        if let Pattern::Variant { name, bindings } = pattern {
+            let symbol = name.get_symbol().unwrap(); // NOTE: the parser only allows identifiers.
+            let variant = match env.as_ref().borrow().get(symbol) {
+                Some(Object::EnumConstructor(variant)) => variant,
+                Some(Object::EnumValue(value)) if value.payload.is_empty() => value.variant.clone(),
+                _ => {
+                    return Err(EvaluationError::Runtime(format!(
+                        "'{name}' is not an enum variant.\n[line {line}]"
+                    )))
+                }
+            };
+            if bindings.len() != variant.arity {
+                return Err(EvaluationError::Runtime(format!(
+                    "Pattern '{name}' has {} bindings but the variant has {} fields.\n[line {line}]",
+                    bindings.len(),
+                    variant.arity
+                )));
+            }
+            let Object::EnumValue(value) = subject else {
+                return Ok(None);
+            };
+            if !Rc::ptr_eq(&value.variant, &variant) {
+                return Ok(None);
+            }
+            let bound = bindings
+                .iter()
+                .zip(value.payload.iter())
+                .filter(|(binding, _)| binding.get_bytes().unwrap().as_ref() != b"_")
+                .map(|(binding, value)| (binding.get_symbol().unwrap(), value.clone()))
+                .collect();
+            return Ok(Some(bound));
+        }
+        let is_match = match pattern {
+            Pattern::Literal(literal) => {
+                let span = literal.span;
+                let literal = self.evaluate_expression(literal, env)?;
+                let is_equal =
+                    evaluate_infix_operation(Token::EQUALEQUAL, subject.clone(), literal, span)?;
+                is_equal.get_truthy_value()
+            }
+            Pattern::Range { start, end } => {
+                matches!(subject, Object::Number(v) if start <= v && v < end)
+            }
+            Pattern::Wildcard => true,
+            Pattern::Variant { .. } => unreachable!(), // NOTE: matched above.
+        };
+        Ok(is_match.then(Vec::new))
+    }
+
     fn evaluate_if_statement(
         &mut self,
         if_statement: &IfStatement,
         env: Env,
-    ) -> Result<Either<Void, Object>, EvaluationError> {
+    ) -> Result<Either<Completion, Object>, EvaluationError> {
         let expr = &if_statement.expr;
         let val = self.evaluate_expression(expr, env.clone())?;
         let val = val.get_truthy_value();
         if val {
-            if let Right(val) = self.evaluate_stmt(&if_statement.if_block, env.clone())? {
-                return Ok(Right(val));
-            }
+            return self.evaluate_stmt(&if_statement.if_block, env.clone());
         } else if let Some(else_block) = &if_statement.else_block {
-            if let Right(val) = self.evaluate_stmt(else_block, env.clone())? {
-                return Ok(Right(val));
-            }
+            return self.evaluate_stmt(else_block, env.clone());
         }
-        Ok(Either::Left(Void))
+        Ok(Either::Left(Completion::Normal))
     }
 
     pub(crate) fn writer(&self) -> &W {
@@ -640,17 +1605,19 @@ where
 
         let global_env = Rc::new(RefCell::new(Environment::default()));
         use native::clock;
-        global_env.as_ref().borrow_mut().add(
-            b"clock".as_ref().into(),
-            Object::NativeFunction(Rc::new(clock)),
-        );
+        let clock_symbol = self.parser.interner().borrow_mut().intern(b"clock");
+        global_env
+            .as_ref()
+            .borrow_mut()
+            .add(clock_symbol, Object::NativeFunction(Rc::new(clock)));
 
         for stmt in statements.iter() {
             match self.evaluate_stmt(stmt, global_env.clone())? {
                 Either::Left(_) => (),
                 Either::Right(_) => {
                     return Err(EvaluationError::Runtime(format!(
-                        "return statements can only be in functions"
+                        "return statements can only be in functions\n[line {}]",
+                        stmt.span.line
                     )))
                 }
             }
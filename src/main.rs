@@ -1,20 +1,27 @@
 use std::env;
-use std::fs;
-use std::io::{self, Write};
+use std::fs::File;
+use std::io::{self, Read};
 
 use interpreter::Interpreter;
-use parser::expression::Precedence;
 use parser::Parser;
 use token::Scanner;
 
 pub(crate) mod interpreter;
 pub(crate) mod parser;
+pub(crate) mod symbol;
 pub(crate) mod token;
+pub(crate) mod xid;
 
 #[cfg(test)]
 pub(crate) mod tests;
 
-struct Void;
+/// How a statement completed when it didn't `return` a value, `Break` and `Continue` unwind up to
+/// the enclosing loop.
+enum Completion {
+    Normal,
+    Break,
+    Continue,
+}
 pub(crate) enum Either<L, R> {
     Left(L),
     Right(R),
@@ -23,18 +30,50 @@ pub(crate) enum Either<L, R> {
 fn main() {
     let args: Vec<String> = env::args().collect();
     if args.len() < 3 {
-        writeln!(io::stderr(), "Usage: {} tokenize <filename>", args[0]).unwrap();
-        return;
+        eprintln!(
+            "Usage: {} tokenize [--format text|json] <filename>",
+            args[0]
+        );
+        std::process::exit(64);
     }
 
     let command = &args[1];
-    let filename = &args[2];
-    let read_contents = || {
-        let file_contents = fs::read_to_string(filename).unwrap_or_else(|_| {
-            writeln!(io::stderr(), "Failed to read file {}", filename).unwrap();
-            String::new()
-        });
-        file_contents
+    let mut filename = None;
+    let mut format = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--format" => match options.next().map(String::as_str) {
+                Some(value @ ("text" | "json")) => format = Some(value),
+                value => {
+                    eprintln!("Unknown format: {}", value.unwrap_or(""));
+                    std::process::exit(64);
+                }
+            },
+            _ => filename = Some(option),
+        }
+    }
+    // NOTE: only the tokens have a json form, the other commands print text.
+    if format.is_some() && command != "tokenize" {
+        eprintln!("Unknown option for {command}: --format");
+        std::process::exit(64);
+    }
+    let Some(filename) = filename else {
+        eprintln!("Missing filename");
+        std::process::exit(64);
+    };
+    // NOTE: the source is scanned while it's being read, `-` reads it from stdin.
+    let open_source = || -> Box<dyn Read> {
+        if filename == "-" {
+            return Box::new(io::stdin());
+        }
+        match File::open(filename) {
+            Ok(file) => Box::new(file),
+            Err(_) => {
+                eprintln!("Failed to read file {}", filename);
+                Box::new(io::empty())
+            }
+        }
     };
 
     match command.as_str() {
@@ -43,29 +82,30 @@ fn main() {
             // writeln!(io::stderr(), "Logs from your program will appear here!").unwrap();
 
             let mut found_lexical_error = false;
-            let file_contents = read_contents();
-            if !file_contents.is_empty() {
-                let scanner = Scanner::new(file_contents);
-                for token in scanner.iter() {
-                    match token {
-                        Ok(token) => println!("{:?}", token),
-                        Err(token) => {
-                            found_lexical_error = true;
+            let scanner = Scanner::from_reader(open_source());
+            // NOTE: the json format prints one object per line, errors go to stderr like in text.
+            let json = format == Some("json");
+            for token in scanner.iter() {
+                match token {
+                    Ok(token) if json => println!("{}", token.to_json()),
+                    Ok(token) => println!("{:?}", token.token),
+                    Err(token) => {
+                        found_lexical_error = true;
+                        if json {
+                            eprintln!("{}", token.to_json())
+                        } else {
                             eprintln!("{:?}", token)
                         }
                     }
                 }
-            } else {
-                println!("EOF  null"); // Placeholder, remove this line when implementing the scanner
             }
             if found_lexical_error {
                 std::process::exit(65);
             }
         }
         "parse" => {
-            let file_contents = read_contents();
             let mut found_err = false;
-            let mut parser = match Parser::from_source(file_contents) {
+            let mut parser = match Parser::from_reader(open_source()) {
                 Ok(parser) => parser,
                 Err(e) => {
                     eprintln!("{:?}", e);
@@ -74,7 +114,7 @@ fn main() {
             };
 
             // let mut parser = Parser::from_source(file_contents).unwrap();
-            match parser.parse_expression(Precedence::Lowest) {
+            match parser.parse_standalone_expression() {
                 Ok(expr) => println!("{:?}", expr),
                 Err(e) => {
                     found_err = true;
@@ -86,9 +126,8 @@ fn main() {
             }
         }
         "evaluate" => {
-            let file_contents = read_contents();
             let stdout = std::io::stdout();
-            let mut interpreter = match Interpreter::from_source(file_contents, stdout) {
+            let mut interpreter = match Interpreter::from_reader(open_source(), stdout) {
                 Ok(interpreter) => interpreter,
                 Err(e) => {
                     eprintln!("{:?}", e);
@@ -104,29 +143,27 @@ fn main() {
             }
         }
         "parse-program" => {
-            let source = read_contents();
-            let mut parser = match Parser::from_source(source) {
+            let mut parser = match Parser::from_reader(open_source()) {
                 Err(e) => {
                     eprintln!("{:?}", e);
-                    std::process::exit(1);
+                    std::process::exit(65);
                 }
                 Ok(parser) => parser,
             };
-            let program = match parser.parse_program() {
-                Err(e) => {
+            let (program, errors) = parser.parse_program_with_errors();
+            if !errors.is_empty() {
+                for e in errors.iter() {
                     eprintln!("{:?}", e);
-                    std::process::exit(1);
                 }
-                Ok(program) => program,
-            };
+                std::process::exit(65);
+            }
 
             for statement in program.iter() {
                 println!("{:?}", statement);
             }
         }
         "run" => {
-            let source = read_contents();
-            let mut interpreter = match Interpreter::from_source(source, std::io::stdout()) {
+            let mut interpreter = match Interpreter::from_reader(open_source(), std::io::stdout()) {
                 Ok(i) => i,
                 Err(e) => {
                     eprintln!("{:?}", e);
@@ -142,8 +179,8 @@ fn main() {
             }
         }
         _ => {
-            writeln!(io::stderr(), "Unknown command: {}", command).unwrap();
-            return;
+            eprintln!("Unknown command: {}", command);
+            std::process::exit(64);
         }
     }
 }
@@ -2,9 +2,24 @@ use std::rc::Rc;
 
 use bytes::Bytes;
 
-use crate::token::Token;
+use crate::{
+    symbol::Symbol,
+    token::{Span, Token},
+};
 
-pub(crate) enum Expression {
+pub(crate) struct Expression {
+    pub(crate) kind: ExpressionKind,
+    /// From the first to the last token of the expression.
+    pub(crate) span: Span,
+}
+
+impl Expression {
+    pub(crate) fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+pub(crate) enum ExpressionKind {
     NilLiteral,
     // NOTE: I had to add the "Print" expression because of majorly one reason.
     // 1. Lox's "and"/"or" operator works like javascript's. that means below is a valid expression
@@ -15,12 +30,23 @@ pub(crate) enum Expression {
     BooleanLiteral(bool),
     NumberLiteral(f64),
     StringLiteral(Bytes),
-    Ident(Bytes),
+    // NOTE: literal parts of the string are kept as `StringLiteral`s, in source order with the
+    // embedded expressions.
+    InterpolatedString(Vec<Expression>),
+    // NOTE: the name is only kept around for printing, the interpreter goes by the symbol.
+    Ident(Symbol, Bytes),
     GroupedExpression(Box<Expression>),
     PrefixExpression {
         operator: Token,
         expr: Box<Expression>,
     },
+    // NOTE: `++` and `--`, the prefix ones are `PrefixExpression`s.
+    PostfixExpression {
+        operator: Token,
+        expr: Box<Expression>,
+    },
+    // NOTE: assignments, compound ones included, are infix expressions with an `EQUAL`,
+    // `PLUSEQUAL`, ... operator.
     InfixExpression {
         operator: Token,
         left_expr: Box<Expression>,
@@ -28,25 +54,90 @@ pub(crate) enum Expression {
     },
     Function(Rc<FunctionExpression>),
     Call(CallExpression),
+    // NOTE: a property set is an assignment, i.e. an `InfixExpression` with a `Get` on the left.
+    Get {
+        object: Box<Expression>,
+        property: Token,
+    },
+    This(Symbol),
+    // NOTE: `condition ? then_branch : else_branch`.
+    Conditional {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+    },
+    List(Vec<Expression>),
+    // NOTE: the entries as (key, value) pairs, in source order.
+    Map(Vec<(Expression, Expression)>),
+    // NOTE: like `Get`, an index set is an assignment with an `Index` on the left.
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+    },
 }
 pub(crate) struct CallExpression {
     pub(crate) callee: Box<Expression>,
-    pub(crate) arguments: Option<Vec<Expression>>,
+    pub(crate) arguments: Option<Vec<Argument>>,
+}
+
+/// An argument of a call, `name: value` for a named one and `...value` for a list spread into
+/// positional arguments. Named arguments come after the positional ones.
+pub(crate) struct Argument {
+    pub(crate) name: Option<Token>,
+    pub(crate) value: Expression,
+    pub(crate) is_spread: bool,
+}
+
+impl std::fmt::Debug for Argument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name}: {:?}", self.value),
+            None if self.is_spread => write!(f, "...{:?}", self.value),
+            None => write!(f, "{:?}", self.value),
+        }
+    }
+}
+
+pub(crate) struct ClassDeclaration {
+    pub(crate) name: Token,
+    pub(crate) methods: Vec<Rc<FunctionExpression>>,
+}
+
+/// `enum Name { Variant(field, ...), ... }`, whose variants are constructors of tagged values.
+pub(crate) struct EnumDeclaration {
+    pub(crate) name: Token,
+    pub(crate) variants: Vec<EnumVariant>,
+}
+
+pub(crate) struct EnumVariant {
+    pub(crate) name: Token,
+    // NOTE: empty for a variant without a payload, which is a value rather than a constructor.
+    pub(crate) fields: Vec<Token>,
 }
 
 pub(crate) struct FunctionExpression {
     pub(crate) name: Option<Token>,
-    pub(crate) parameters: Option<Vec<Token>>,
+    pub(crate) parameters: Option<Vec<Parameter>>,
     pub(crate) body: Vec<Statement>,
 }
 
+/// A parameter of a function, `name = default` for one that can be left out of a call and
+/// `...name` for the last one collecting the extra positional arguments into a list.
+pub(crate) struct Parameter {
+    pub(crate) name: Token,
+    // NOTE: evaluated at each call that leaves the parameter out, in the function's environment
+    // with the preceding parameters bound.
+    pub(crate) default: Option<Expression>,
+    pub(crate) is_rest: bool,
+}
+
 impl std::fmt::Debug for FunctionExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let FunctionExpression { name, .. } = self;
         write!(f, "<fn")?;
         match name {
             Some(name) => match name {
-                Token::Identifier(name) => {
+                Token::Identifier(_, name) => {
                     let name = unsafe { std::str::from_utf8_unchecked(name) };
                     write!(f, " {name}>")?;
                 }
@@ -59,30 +150,46 @@ impl std::fmt::Debug for FunctionExpression {
 }
 
 impl std::fmt::Debug for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.kind)
+    }
+}
+
+impl std::fmt::Debug for ExpressionKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expression::NilLiteral => write!(f, "nil"),
-            Expression::BooleanLiteral(v) => write!(f, "{}", v),
-            Expression::NumberLiteral(v) => write!(f, "{:?}", v),
-            Expression::StringLiteral(bytes) => {
+            ExpressionKind::NilLiteral => write!(f, "nil"),
+            ExpressionKind::BooleanLiteral(v) => write!(f, "{}", v),
+            ExpressionKind::NumberLiteral(v) => write!(f, "{:?}", v),
+            ExpressionKind::StringLiteral(bytes) => {
                 let str = unsafe { std::str::from_utf8_unchecked(bytes.as_ref()) };
                 write!(f, "{}", str)
             }
-            Expression::GroupedExpression(e) => write!(f, "(group {:?})", e),
-            Expression::PrefixExpression { operator, expr } => {
+            ExpressionKind::InterpolatedString(parts) => {
+                write!(f, "(interpolate")?;
+                for part in parts.iter() {
+                    write!(f, " {part:?}")?;
+                }
+                write!(f, ")")
+            }
+            ExpressionKind::GroupedExpression(e) => write!(f, "(group {:?})", e),
+            ExpressionKind::PrefixExpression { operator, expr } => {
                 write!(f, "({} {:?})", operator, expr)
             }
-            Expression::InfixExpression {
+            ExpressionKind::PostfixExpression { operator, expr } => {
+                write!(f, "({:?} {})", expr, operator)
+            }
+            ExpressionKind::InfixExpression {
                 operator,
                 left_expr,
                 right_expr,
             } => write!(f, "({operator} {:?} {:?})", left_expr, right_expr),
-            Expression::Ident(ident_bytes) => write!(f, "ident: {}", unsafe {
+            ExpressionKind::Ident(_, ident_bytes) => write!(f, "ident: {}", unsafe {
                 std::str::from_utf8_unchecked(ident_bytes.as_ref())
             }),
-            Expression::Print(e) => write!(f, "print {:?}", e.as_ref()),
-            Expression::Function(fe) => write!(f, "{fe:?}", fe = fe.as_ref()),
-            Expression::Call(CallExpression { callee, arguments }) => {
+            ExpressionKind::Print(e) => write!(f, "print {:?}", e.as_ref()),
+            ExpressionKind::Function(fe) => write!(f, "{fe:?}", fe = fe.as_ref()),
+            ExpressionKind::Call(CallExpression { callee, arguments }) => {
                 write!(f, "{callee:?}(", callee = callee.as_ref())?;
                 if let Some(args) = arguments {
                     let args_count = args.len();
@@ -95,6 +202,34 @@ impl std::fmt::Debug for Expression {
                 }
                 write!(f, ")")
             }
+            ExpressionKind::Get { object, property } => write!(f, "{object:?}.{property}"),
+            ExpressionKind::This(_) => write!(f, "this"),
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => write!(f, "(?: {condition:?} {then_branch:?} {else_branch:?})"),
+            ExpressionKind::List(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element:?}")?;
+                }
+                write!(f, "]")
+            }
+            ExpressionKind::Map(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key:?}: {value:?}")?;
+                }
+                write!(f, "}}")
+            }
+            ExpressionKind::Index { object, index } => write!(f, "{object:?}[{index:?}]"),
         }
     }
 }
@@ -103,14 +238,19 @@ impl std::fmt::Debug for Expression {
 pub(crate) enum Precedence {
     Lowest = 1,
     Assign = 2,
-    Equals = 3,
-    Or = 4,
-    And = 5,
-    LessGreater = 6,
-    Sum = 7,
-    Product = 8,
-    Prefix = 9,
-    Call = 10,
+    // NOTE: below `Equals` and `Or` so that `a == b ? x : y` and `a or b ? x : y` branch on the
+    // whole `a == b` and `a or b`.
+    Conditional = 3,
+    Equals = 4,
+    Or = 5,
+    And = 6,
+    LessGreater = 7,
+    Sum = 8,
+    Product = 9,
+    Prefix = 10,
+    // NOTE: above `Prefix` so that `-2 ** 2` is `-(2 ** 2)`.
+    Exponent = 11,
+    Call = 12,
 }
 
 impl Precedence {
@@ -120,7 +260,8 @@ impl Precedence {
 }
 
 pub(crate) struct VarDeclaration {
-    pub(crate) identifier: Bytes,
+    pub(crate) identifier: Symbol,
+    pub(crate) name: Bytes,
     pub(crate) expr: Option<Expression>,
 }
 
@@ -138,9 +279,70 @@ pub(crate) struct IfStatement {
 pub(crate) struct WhileLoop {
     pub(crate) expr: Option<Expression>,
     pub(crate) block: Box<Statement>,
+    // NOTE: the increment of a desugared `for` loop, kept out of the block so that `continue`
+    // doesn't skip it.
+    pub(crate) increment: Option<Expression>,
+}
+
+/// `match subject { pattern => statement ... }`, which runs the statement of the first arm whose
+/// pattern matches the subject and whose guard, if any, is truthy.
+pub(crate) struct MatchStatement {
+    pub(crate) subject: Expression,
+    pub(crate) arms: Vec<MatchArm>,
+}
+
+pub(crate) struct MatchArm {
+    // NOTE: `1 | 2 => ...` matches either pattern.
+    pub(crate) patterns: Vec<Pattern>,
+    pub(crate) guard: Option<Expression>,
+    pub(crate) body: Statement,
+}
+
+pub(crate) enum Pattern {
+    // NOTE: a number, string, boolean or `nil` literal, matching a value equal to it.
+    Literal(Expression),
+    // NOTE: `start..end` matches the numbers from `start` up to, but not including, `end`.
+    Range { start: f64, end: f64 },
+    Wildcard,
+    // NOTE: `Rect(w, h)` matches a `Rect` value, binding its payload to `w` and `h` in the arm,
+    // `_` skips a field.
+    Variant { name: Token, bindings: Vec<Token> },
+}
+
+impl std::fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Literal(literal) => write!(f, "{literal:?}"),
+            Pattern::Range { start, end } => write!(f, "{start:?}..{end:?}"),
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Variant { name, bindings } if bindings.is_empty() => write!(f, "{name}"),
+            Pattern::Variant { name, bindings } => {
+                write!(f, "{name}(")?;
+                for (index, binding) in bindings.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{binding}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+pub(crate) struct Statement {
+    pub(crate) kind: StatementKind,
+    /// From the first to the last token of the statement, the terminating `;` excluded.
+    pub(crate) span: Span,
 }
 
-pub(crate) enum Statement {
+impl Statement {
+    pub(crate) fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+pub(crate) enum StatementKind {
     Expression(Expression),
     Print(Expression),
     VarDeclaration(VarDeclaration),
@@ -149,9 +351,14 @@ pub(crate) enum Statement {
     IfStatement(Box<IfStatement>),
     WhileLoop(WhileLoop),
     Return(Expression),
+    Class(ClassDeclaration),
+    Break,
+    Continue,
+    Match(Box<MatchStatement>),
+    Enum(EnumDeclaration),
 }
 
-impl Statement {
+impl StatementKind {
     fn print_statements(
         &self,
         f: &mut std::fmt::Formatter<'_>,
@@ -159,8 +366,8 @@ impl Statement {
         statements: &Vec<Statement>,
     ) -> std::fmt::Result {
         for stmt in statements.iter() {
-            match &stmt {
-                Statement::Block(stms) => {
+            match &stmt.kind {
+                StatementKind::Block(stms) => {
                     write!(f, "{prefix_whitespace}{{\n")?;
                     let _ =
                         self.print_statements(f, format!("{prefix_whitespace}  ").as_str(), &stms)?;
@@ -174,29 +381,35 @@ impl Statement {
 }
 
 impl std::fmt::Debug for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.kind)
+    }
+}
+
+impl std::fmt::Debug for StatementKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Statement::Expression(e) => match e {
-                Expression::Function(_) => write!(f, "{e:?}"),
+            StatementKind::Expression(e) => match e.kind {
+                ExpressionKind::Function(_) => write!(f, "{e:?}"),
                 _ => write!(f, "{:?};", e),
             },
 
-            Statement::Print(e) => write!(f, "print {:?};", e),
-            Statement::VarDeclaration(VarDeclaration { identifier, expr }) => {
-                let identifier = unsafe { std::str::from_utf8_unchecked(identifier.as_ref()) };
+            StatementKind::Print(e) => write!(f, "print {:?};", e),
+            StatementKind::VarDeclaration(VarDeclaration { name, expr, .. }) => {
+                let identifier = unsafe { std::str::from_utf8_unchecked(name.as_ref()) };
                 match expr {
                     Some(expr) => write!(f, "var {} = {:?};", identifier, expr),
                     None => write!(f, "var {};", identifier),
                 }
             }
-            Statement::Block(statements) => {
+            StatementKind::Block(statements) => {
                 write!(f, "{{\n")?;
                 self.print_statements(f, "  ", statements)?;
                 write!(f, "}}")?;
 
                 Ok(())
             }
-            Statement::IfStatement(stmt) => {
+            StatementKind::IfStatement(stmt) => {
                 let IfStatement {
                     if_block,
                     expr,
@@ -210,10 +423,61 @@ impl std::fmt::Debug for Statement {
                 };
                 write!(f, " else {:?}\n", else_block)
             }
-            Statement::WhileLoop(WhileLoop { expr, block }) => {
-                write!(f, "while ( {:?} ) {:?}", expr, block)
+            StatementKind::WhileLoop(WhileLoop {
+                expr,
+                block,
+                increment: None,
+            }) => write!(f, "while ( {:?} ) {:?}", expr, block),
+            StatementKind::WhileLoop(WhileLoop {
+                expr,
+                block,
+                increment: Some(increment),
+            }) => write!(f, "while ( {:?}; {:?} ) {:?}", expr, increment, block),
+            StatementKind::Match(stmt) => {
+                write!(f, "match {:?} {{", stmt.subject)?;
+                for arm in stmt.arms.iter() {
+                    write!(f, " ")?;
+                    for (index, pattern) in arm.patterns.iter().enumerate() {
+                        if index != 0 {
+                            write!(f, " | ")?;
+                        }
+                        write!(f, "{pattern:?}")?;
+                    }
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {guard:?}")?;
+                    }
+                    write!(f, " => {:?}", arm.body)?;
+                }
+                write!(f, " }}")
+            }
+            StatementKind::Enum(EnumDeclaration { name, variants }) => {
+                write!(f, "enum {name} {{")?;
+                for (index, variant) in variants.iter().enumerate() {
+                    write!(f, "{}", if index == 0 { " " } else { ", " })?;
+                    write!(f, "{}", variant.name)?;
+                    if !variant.fields.is_empty() {
+                        write!(f, "(")?;
+                        for (index, field) in variant.fields.iter().enumerate() {
+                            if index != 0 {
+                                write!(f, ", ")?;
+                            }
+                            write!(f, "{field}")?;
+                        }
+                        write!(f, ")")?;
+                    }
+                }
+                write!(f, " }}")
+            }
+            StatementKind::Break => write!(f, "break;"),
+            StatementKind::Continue => write!(f, "continue;"),
+            StatementKind::Return(e) => write!(f, "return {e:?}"),
+            StatementKind::Class(ClassDeclaration { name, methods }) => {
+                write!(f, "class {name} {{")?;
+                for method in methods.iter() {
+                    write!(f, " {method:?}")?;
+                }
+                write!(f, " }}")
             }
-            Statement::Return(e) => write!(f, "return {e:?}"),
         }
     }
 }
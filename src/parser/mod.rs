@@ -1,26 +1,54 @@
 #![allow(dead_code)]
 
-use std::rc::Rc;
+use std::{cell::RefCell, collections::VecDeque, io::Read, rc::Rc};
+
+use bytes::Bytes;
 
 use expression::{
-    CallExpression, Expression, FunctionExpression, IfStatement, Precedence, Statement,
-    VarDeclaration, WhileLoop,
+    Argument, CallExpression, ClassDeclaration, EnumDeclaration, EnumVariant, Expression,
+    ExpressionKind, FunctionExpression, IfStatement, MatchArm, MatchStatement, Parameter, Pattern,
+    Precedence, Statement, StatementKind, VarDeclaration, WhileLoop,
 };
 
-use crate::token::{LexicalError, Scanner, Token, TokenIterator};
+use crate::{
+    symbol::Interner,
+    token::{LexicalError, Scanner, Span, SpannedToken, Token, TokenIterator},
+};
 pub(crate) mod expression;
 
 pub(crate) struct Parser {
     _scanner: Scanner,
     _token_iterator: TokenIterator,
     curr_token: Token,
+    curr_span: Span,
+    // NOTE: span of the token before `curr_token`, where a statement that has been advanced past
+    // ends.
+    prev_span: Span,
     peek_token: Token,
+    peek_span: Span,
+    // NOTE: tokens after the peek token that have been scanned to look further ahead.
+    lookahead: VecDeque<SpannedToken>,
+    lexical_errors: Vec<LexicalError>,
+    parse_errors: Vec<ParseError>,
+    // NOTE: number of loops around the statement being parsed, within the innermost function.
+    loop_depth: u32,
+    // NOTE: number of classes around the expression being parsed, `this` is only valid within
+    // one.
+    class_depth: u32,
+    // NOTE: whether the innermost function is an `init` method, which can only `return;`.
+    in_initializer: bool,
+    // NOTE: false directly within the guard of a match arm, where `x =>` ends the guard instead
+    // of starting an arrow function, see `parse_nested`.
+    allow_arrow_functions: bool,
+    interner: Rc<RefCell<Interner>>,
 }
 
 pub(crate) enum ParseError {
     EmptySource,
     ImpossibleError,
-    LexicalError(LexicalError),
+    LexicalErrors(Vec<LexicalError>),
+    // NOTE: only used when there is more than one error, a single error is returned as is.
+    Multiple(Vec<ParseError>),
     ExpectedTokenNotFound {
         expected: &'static str,
         got: Token,
@@ -30,7 +58,23 @@ pub(crate) enum ParseError {
         at: Token,
     },
     UnmatchedParentheses,
-    InvalidAssignmentTarget,
+    InvalidAssignmentTarget {
+        operator: Token,
+    },
+    PositionalAfterNamedArgument {
+        line: u32,
+    },
+    OutsideOfLoop {
+        keyword: Token,
+        line: u32,
+    },
+    OutsideOfClass {
+        keyword: Token,
+        line: u32,
+    },
+    ReturnValueFromInitializer {
+        line: u32,
+    },
 }
 
 impl std::fmt::Debug for ParseError {
@@ -38,15 +82,56 @@ impl std::fmt::Debug for ParseError {
         match self {
             ParseError::EmptySource => write!(f, "EmptySource"),
             ParseError::ImpossibleError => write!(f, "ImpossibleError"),
-            ParseError::LexicalError(e) => write!(f, "{:?}", e),
+            ParseError::LexicalErrors(errors) => {
+                for (index, e) in errors.iter().enumerate() {
+                    if index != 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{:?}", e)?;
+                }
+                Ok(())
+            }
+            ParseError::Multiple(errors) => {
+                for (index, e) in errors.iter().enumerate() {
+                    if index != 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{:?}", e)?;
+                }
+                Ok(())
+            }
             ParseError::ExpectedTokenNotFound {
                 line,
                 got,
                 expected,
             } => write!(f, "[line {line}] Error at '{got}': expect {expected}"),
             ParseError::UnmatchedParentheses => write!(f, "Error: Unmatched parentheses."),
-            ParseError::InvalidAssignmentTarget => {
-                write!(f, "Error at '=': Invalid assignment target.")
+            ParseError::InvalidAssignmentTarget { operator } => {
+                write!(f, "Error at '{operator}': Invalid assignment target.")
+            }
+            ParseError::PositionalAfterNamedArgument { line } => {
+                write!(
+                    f,
+                    "[line {line}] Error: Positional arguments must come before named arguments."
+                )
+            }
+            ParseError::OutsideOfLoop { keyword, line } => {
+                write!(
+                    f,
+                    "[line {line}] Error at '{keyword}': Can't use '{keyword}' outside of a loop."
+                )
+            }
+            ParseError::OutsideOfClass { keyword, line } => {
+                write!(
+                    f,
+                    "[line {line}] Error at '{keyword}': Can't use '{keyword}' outside of a class."
+                )
+            }
+            ParseError::ReturnValueFromInitializer { line } => {
+                write!(
+                    f,
+                    "[line {line}] Error at 'return': Can't return a value from an initializer."
+                )
             }
             ParseError::TooManyArguments { at } => {
                 write!(f, "Error at '{at}': Can't have more than 255 arguments.")
@@ -59,31 +144,61 @@ type ParseResult<T> = Result<T, ParseError>;
 
 impl Parser {
     pub(crate) fn from_source(source: String) -> Result<Self, ParseError> {
-        let scanner = Scanner::new(source);
+        Self::from_scanner(Scanner::new(source))
+    }
+
+    /// A parser over a source that is scanned from `reader` while parsing.
+    pub(crate) fn from_reader<R: Read + 'static>(reader: R) -> Result<Self, ParseError> {
+        Self::from_scanner(Scanner::from_reader(reader))
+    }
+
+    fn from_scanner(scanner: Scanner) -> Result<Self, ParseError> {
         let mut token_iterator = scanner.iter();
+        let mut lexical_errors = vec![];
 
-        let curr_token = token_iterator
-            .next()
-            .ok_or(ParseError::EmptySource)?
-            .map_err(|e| ParseError::LexicalError(e))?;
+        let SpannedToken {
+            token: curr_token,
+            span: curr_span,
+            ..
+        } = next_token(&mut token_iterator, &mut lexical_errors);
 
         if let Token::EOF = curr_token {
+            if !lexical_errors.is_empty() {
+                return Err(ParseError::LexicalErrors(lexical_errors));
+            }
             return Err(ParseError::EmptySource);
         }
 
-        let peek_token = token_iterator
-            .next()
-            .ok_or_else(|| unreachable!())?
-            .map_err(|e| ParseError::LexicalError(e))?;
+        let SpannedToken {
+            token: peek_token,
+            span: peek_span,
+            ..
+        } = next_token(&mut token_iterator, &mut lexical_errors);
 
         Ok(Self {
+            interner: scanner.interner(),
             _scanner: scanner,
             _token_iterator: token_iterator,
             curr_token,
+            curr_span,
+            prev_span: curr_span,
             peek_token,
+            peek_span,
+            lookahead: VecDeque::new(),
+            lexical_errors,
+            parse_errors: vec![],
+            loop_depth: 0,
+            class_depth: 0,
+            in_initializer: false,
+            allow_arrow_functions: true,
         })
     }
 
+    /// The interner of the identifiers in the parsed source, to map symbols back to names.
+    pub(crate) fn interner(&self) -> Rc<RefCell<Interner>> {
+        self.interner.clone()
+    }
+
     pub(crate) fn get_curr_line(&self) -> u32 {
         self._token_iterator.get_curr_line()
     }
@@ -94,21 +209,66 @@ impl Parser {
         } else {
             true
         };
+        self.prev_span = self.curr_span;
         std::mem::swap(&mut self.curr_token, &mut self.peek_token);
+        std::mem::swap(&mut self.curr_span, &mut self.peek_span);
         if should_forward_peek_token {
-            // TODO: remove unwraps
-            self.peek_token = self._token_iterator.next().unwrap().unwrap();
+            let SpannedToken { token, span, .. } = match self.lookahead.pop_front() {
+                Some(token) => token,
+                None => next_token(&mut self._token_iterator, &mut self.lexical_errors),
+            };
+            self.peek_token = token;
+            self.peek_span = span;
         } else {
             self.peek_token = Token::EOF;
         }
     }
 
+    /// The token `n` tokens after the peek token, `peek_nth(0)` being the peek token itself.
+    fn peek_nth(&mut self, n: usize) -> &Token {
+        if n == 0 || matches!(self.peek_token, Token::EOF) {
+            return &self.peek_token;
+        }
+        while self.lookahead.len() < n {
+            if let Some(SpannedToken {
+                token: Token::EOF, ..
+            }) = self.lookahead.back()
+            {
+                break;
+            }
+            let token = next_token(&mut self._token_iterator, &mut self.lexical_errors);
+            self.lookahead.push_back(token);
+        }
+        match self.lookahead.get(n - 1) {
+            Some(SpannedToken { token, .. }) => token,
+            None => &self.lookahead.back().unwrap().token,
+        }
+    }
+
+    /// Span from the start of `start` to the end of the current token.
+    fn span_from(&self, start: Span) -> Span {
+        Span {
+            end: self.curr_span.end,
+            ..start
+        }
+    }
+
+    /// Span from the start of `start` to the end of the previous token, for statements that have
+    /// been advanced past.
+    fn span_until_prev(&self, start: Span) -> Span {
+        Span {
+            end: self.prev_span.end,
+            ..start
+        }
+    }
+
     fn parse_prefix_grouped_expression(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
         if let Token::RParen = self.peek_token {
             return Err(ParseError::ExpectedTokenNotFound {
                 expected: "expression",
                 got: Token::RParen,
-                line: self._token_iterator.get_curr_line(),
+                line: self.peek_span.line,
             });
         }
         self.advance_token();
@@ -118,17 +278,78 @@ impl Parser {
             return Err(ParseError::UnmatchedParentheses);
         };
         self.advance_token();
-        Ok(Expression::GroupedExpression(Box::new(expr)))
+        Ok(Expression::new(
+            ExpressionKind::GroupedExpression(Box::new(expr)),
+            self.span_from(start),
+        ))
     }
 
     fn parse_prefix_operator_expression(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
         let operator = self.curr_token.clone();
         self.advance_token();
         let expression = self.parse_expression(Precedence::Prefix)?;
-        Ok(Expression::PrefixExpression {
-            operator: operator,
-            expr: Box::new(expression),
-        })
+        Ok(Expression::new(
+            ExpressionKind::PrefixExpression {
+                operator,
+                expr: Box::new(expression),
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// `++x` and `--x`, whose operand must be something that can be assigned to.
+    fn parse_prefix_update_expression(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        let operator = self.curr_token.clone();
+        self.advance_token();
+        let target = self.parse_expression(Precedence::Prefix)?;
+        ensure_assignment_target(&target, &operator)?;
+        Ok(Expression::new(
+            ExpressionKind::PrefixExpression {
+                operator,
+                expr: Box::new(target),
+            },
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_postfix_update_expression(&mut self, target: Expression) -> ParseResult<Expression> {
+        let operator = self.curr_token.clone();
+        ensure_assignment_target(&target, &operator)?;
+        let span = self.span_from(target.span);
+        Ok(Expression::new(
+            ExpressionKind::PostfixExpression {
+                operator,
+                expr: Box::new(target),
+            },
+            span,
+        ))
+    }
+
+    fn parse_conditional_expression(&mut self, condition: Expression) -> ParseResult<Expression> {
+        self.advance_token();
+        let then_branch = self.parse_expression(Precedence::Lowest)?;
+        let Token::COLON = self.peek_token else {
+            return Err(ParseError::ExpectedTokenNotFound {
+                expected: ": after the then branch of a conditional expression",
+                got: self.peek_token.clone(),
+                line: self.peek_span.line,
+            });
+        };
+        self.advance_token();
+        self.advance_token();
+        // NOTE: `?:` is right associative, so the else branch takes in further `?:`s.
+        let else_branch = self.parse_expression(Precedence::Assign)?;
+        let span = self.span_from(condition.span);
+        Ok(Expression::new(
+            ExpressionKind::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            },
+            span,
+        ))
     }
 
     fn parse_infix_operator_expression(
@@ -137,18 +358,27 @@ impl Parser {
     ) -> ParseResult<Expression> {
         let operator = self.curr_token.clone();
         self.advance_token();
-        let right_expr = self.parse_expression(operator.get_precedence())?;
-        Ok(Expression::InfixExpression {
-            operator: operator,
-            left_expr: Box::new(left_expr),
-            right_expr: Box::new(right_expr),
-        })
+        let precedence = match operator {
+            // NOTE: `**` is right associative, so the right operand takes in further `**`s.
+            Token::STARSTAR => Precedence::Prefix,
+            _ => operator.get_precedence(),
+        };
+        let right_expr = self.parse_expression(precedence)?;
+        let span = self.span_from(left_expr.span);
+        Ok(Expression::new(
+            ExpressionKind::InfixExpression {
+                operator,
+                left_expr: Box::new(left_expr),
+                right_expr: Box::new(right_expr),
+            },
+            span,
+        ))
     }
 
     #[allow(unused_variables)]
     fn parse_call_expression(&mut self, left_expr: Expression) -> ParseResult<Expression> {
         self.advance_token();
-        let mut args: Vec<Expression> = vec![];
+        let mut args: Vec<Argument> = vec![];
         loop {
             if let Token::RParen = &self.curr_token {
                 // self.advance_token();
@@ -159,7 +389,26 @@ impl Parser {
                     at: self.curr_token.clone(),
                 });
             }
-            let arg = self.parse_expression(Precedence::Lowest)?;
+            let name = match (&self.curr_token, &self.peek_token) {
+                (Token::Identifier(..), Token::COLON) => {
+                    let name = self.curr_token.clone();
+                    self.advance_token();
+                    self.advance_token();
+                    Some(name)
+                }
+                _ => None,
+            };
+            let is_spread = name.is_none() && matches!(self.curr_token, Token::DOTDOTDOT);
+            if is_spread {
+                self.advance_token();
+            }
+            // NOTE: a positional argument after a named one would be ambiguous.
+            if name.is_none() && args.iter().any(|arg| arg.name.is_some()) {
+                return Err(ParseError::PositionalAfterNamedArgument {
+                    line: self.curr_span.line,
+                });
+            }
+            let value = self.parse_expression(Precedence::Lowest)?;
             self.advance_token();
 
             match &self.curr_token {
@@ -172,72 +421,137 @@ impl Parser {
                     return Err(ParseError::ExpectedTokenNotFound {
                         expected: "expression",
                         got: token.clone(),
-                        line: self.get_curr_line(),
+                        line: self.curr_span.line,
                     })
                 }
             }
-            args.push(arg);
+            args.push(Argument {
+                name,
+                value,
+                is_spread,
+            });
         }
         let args = match args.len() {
             0 => None,
             _ => Some(args),
         };
-        Ok(Expression::Call(CallExpression {
-            arguments: args,
-            callee: Box::new(left_expr),
-        }))
+        let span = self.span_from(left_expr.span);
+        Ok(Expression::new(
+            ExpressionKind::Call(CallExpression {
+                arguments: args,
+                callee: Box::new(left_expr),
+            }),
+            span,
+        ))
     }
 
     fn parse_function_expression(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
         self.advance_token();
+        self.parse_function(start, false)
+    }
+
+    /// Parses the optional name, parameters and body of a function, starting at the name. Shared
+    /// by function expressions, which start at `fun`, and methods, which don't.
+    fn parse_function(&mut self, start: Span, is_initializer: bool) -> ParseResult<Expression> {
         let name: Option<Token>;
-        if let Token::Identifier(_) = &self.curr_token {
+        if let Token::Identifier(..) = &self.curr_token {
             name = Some(self.curr_token.clone());
             self.advance_token();
         } else {
             name = None;
         }
+        let params = self.parse_parameters()?;
+        let body = match &self.curr_token {
+            Token::LBrace => self.parse_function_body(is_initializer)?,
+            token => {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "{",
+                    got: token.clone(),
+                    line: self.curr_span.line,
+                })
+            }
+        };
+        let params = match params.len() {
+            0 => None,
+            _ => Some(params),
+        };
+        Ok(Expression::new(
+            ExpressionKind::Function(Rc::new(FunctionExpression {
+                body,
+                parameters: params,
+                name,
+            })),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses a parenthesized parameter list, leaving the token after the `)` as the current one.
+    fn parse_parameters(&mut self) -> ParseResult<Vec<Parameter>> {
         match &self.curr_token {
             Token::LParen => (),
             token => {
                 return Err(ParseError::ExpectedTokenNotFound {
                     expected: "(",
                     got: token.clone(),
-                    line: self.get_curr_line(),
+                    line: self.curr_span.line,
                 })
             }
         };
         self.advance_token();
+        self.parse_parameter_list(Vec::new())
+    }
 
-        let mut params: Vec<Token> = Vec::new();
-
+    /// Parses the parameters after `params` up to and including the `)`, starting at the next
+    /// parameter or the `)`.
+    fn parse_parameter_list(&mut self, mut params: Vec<Parameter>) -> ParseResult<Vec<Parameter>> {
         loop {
             if let Token::RParen = &self.curr_token {
                 self.advance_token();
                 break;
             }
 
+            let is_rest = matches!(self.curr_token, Token::DOTDOTDOT);
+            if is_rest {
+                self.advance_token();
+            }
             let name_token = match &self.curr_token {
-                Token::Identifier(_) => self.curr_token.clone(),
+                Token::Identifier(..) => self.curr_token.clone(),
                 t => {
                     return Err(ParseError::ExpectedTokenNotFound {
                         expected: "identifier",
                         got: t.clone(),
-                        line: self.get_curr_line(),
+                        line: self.curr_span.line,
                     })
                 }
             };
             self.advance_token();
 
+            let mut default = None;
+            if is_rest {
+                // NOTE: the rest parameter takes whatever is left, so nothing can follow it.
+                if !matches!(self.curr_token, Token::RParen) {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: ") after rest parameter",
+                        got: self.curr_token.clone(),
+                        line: self.curr_span.line,
+                    });
+                }
+            } else if let Token::EQUAL = &self.curr_token {
+                self.advance_token();
+                default = Some(self.parse_expression(Precedence::Lowest)?);
+                self.advance_token();
+            }
+
             match &self.curr_token {
                 Token::RParen => (),
                 Token::COMMA => match &self.peek_token {
-                    Token::Identifier(_) => self.advance_token(),
+                    Token::Identifier(..) | Token::DOTDOTDOT => self.advance_token(),
                     t => {
                         return Err(ParseError::ExpectedTokenNotFound {
                             expected: "identifier",
                             got: t.clone(),
-                            line: self.get_curr_line(),
+                            line: self.peek_span.line,
                         })
                     }
                 },
@@ -245,36 +559,221 @@ impl Parser {
                     return Err(ParseError::ExpectedTokenNotFound {
                         expected: "identifier",
                         got: t.clone(),
-                        line: self.get_curr_line(),
+                        line: self.curr_span.line,
                     })
                 }
             }
-            params.push(name_token);
+            params.push(Parameter {
+                name: name_token,
+                default,
+                is_rest,
+            });
+        }
+        Ok(params)
+    }
+
+    /// Parses the `{ ... }` body of a function, leaving the `}` as the current token.
+    fn parse_function_body(&mut self, is_initializer: bool) -> ParseResult<Vec<Statement>> {
+        // NOTE: a loop around the function doesn't make `break` valid within its body.
+        let enclosing_loop_depth = std::mem::take(&mut self.loop_depth);
+        let enclosing_in_initializer = std::mem::replace(&mut self.in_initializer, is_initializer);
+        let body = self.parse_nested(|parser| parser.parse_block_statement(true));
+        self.loop_depth = enclosing_loop_depth;
+        self.in_initializer = enclosing_in_initializer;
+        let StatementKind::Block(stmts) = body?.kind else {
+            unreachable!();
+        };
+        Ok(stmts)
+    }
+
+    /// Parses something enclosed in brackets, braces or a function body, where arrow functions
+    /// are allowed again even within a match guard.
+    fn parse_nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let enclosing_allow_arrow_functions =
+            std::mem::replace(&mut self.allow_arrow_functions, true);
+        let result = parse(self);
+        self.allow_arrow_functions = enclosing_allow_arrow_functions;
+        result
+    }
+
+    /// Whether the `(` at the current token starts the parameters of an arrow function rather
+    /// than a grouped expression, decided from at most three tokens after it: `()`, `(...`,
+    /// `(a,` and `(a)` followed by `=>` can only start parameters. `(a = ...` can start either,
+    /// see `parse_grouped_expression_or_arrow_function`.
+    fn is_arrow_function_ahead(&mut self) -> bool {
+        match self.peek_token {
+            Token::DOTDOTDOT => true,
+            Token::RParen => matches!(self.peek_nth(1), Token::ARROW),
+            Token::Identifier(..) => match self.peek_nth(1) {
+                Token::COMMA => true,
+                Token::RParen => matches!(self.peek_nth(2), Token::ARROW),
+                _ => false,
+            },
+            _ => false,
         }
+    }
+
+    /// `(a = ...` is either an assignment in parentheses or an arrow function whose first
+    /// parameter has a default value. The assignment is parsed first, and turned into that
+    /// parameter if a `,`, or a `)` followed by `=>`, comes after it.
+    fn parse_grouped_expression_or_arrow_function(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        self.advance_token();
+        let expr = self.parse_expression(Precedence::Lowest)?;
+        let is_parameter = match self.peek_token {
+            Token::COMMA => true,
+            Token::RParen => matches!(self.peek_nth(1), Token::ARROW),
+            _ => false,
+        };
+        if !is_parameter {
+            let Token::RParen = self.peek_token else {
+                return Err(ParseError::UnmatchedParentheses);
+            };
+            self.advance_token();
+            return Ok(Expression::new(
+                ExpressionKind::GroupedExpression(Box::new(expr)),
+                self.span_from(start),
+            ));
+        }
+
+        let ExpressionKind::InfixExpression {
+            operator: Token::EQUAL,
+            left_expr,
+            right_expr,
+        } = expr.kind
+        else {
+            return Err(ParseError::UnmatchedParentheses);
+        };
+        let ExpressionKind::Ident(symbol, name) = left_expr.kind else {
+            unreachable!(); // NOTE: only called when the `(` is followed by an identifier.
+        };
+        let first = Parameter {
+            name: Token::Identifier(symbol, name),
+            default: Some(*right_expr),
+            is_rest: false,
+        };
+        self.advance_token();
+        if let Token::COMMA = self.curr_token {
+            match &self.peek_token {
+                Token::Identifier(..) | Token::DOTDOTDOT => self.advance_token(),
+                t => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "identifier",
+                        got: t.clone(),
+                        line: self.peek_span.line,
+                    })
+                }
+            }
+        }
+        let params = self.parse_parameter_list(vec![first])?;
+        self.parse_arrow_function_body(start, params)
+    }
+
+    /// `x => body` and `(a, b) => body`, where the body is either a block or an expression whose
+    /// value is returned.
+    fn parse_arrow_function(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        let params = match &self.curr_token {
+            Token::Identifier(..) => {
+                let name = self.curr_token.clone();
+                self.advance_token();
+                vec![Parameter {
+                    name,
+                    default: None,
+                    is_rest: false,
+                }]
+            }
+            _ => self.parse_parameters()?,
+        };
+        self.parse_arrow_function_body(start, params)
+    }
+
+    /// Parses the body of an arrow function from the `=>` after its parameters.
+    fn parse_arrow_function_body(
+        &mut self,
+        start: Span,
+        params: Vec<Parameter>,
+    ) -> ParseResult<Expression> {
+        if !matches!(self.curr_token, Token::ARROW) {
+            return Err(ParseError::ExpectedTokenNotFound {
+                expected: "=> after arrow function parameters",
+                got: self.curr_token.clone(),
+                line: self.curr_span.line,
+            });
+        }
+        self.advance_token();
         let body = match &self.curr_token {
-            Token::LBrace => self.parse_block_statement(true)?,
-            token => {
-                return Err(ParseError::ExpectedTokenNotFound {
-                    expected: "{",
-                    got: token.clone(),
-                    line: self.get_curr_line(),
-                })
+            Token::LBrace => self.parse_function_body(false)?,
+            _ => {
+                let enclosing_loop_depth = std::mem::take(&mut self.loop_depth);
+                let expr = self.parse_expression(Precedence::Lowest);
+                self.loop_depth = enclosing_loop_depth;
+                let expr = expr?;
+                vec![Statement::new(
+                    StatementKind::Return(expr),
+                    self.span_from(start),
+                )]
             }
         };
         let params = match params.len() {
             0 => None,
             _ => Some(params),
         };
-        let stmts = if let Statement::Block(stmts) = body {
-            stmts
-        } else {
+        Ok(Expression::new(
+            ExpressionKind::Function(Rc::new(FunctionExpression {
+                body,
+                parameters: params,
+                name: None,
+            })),
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_interpolated_string(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        let mut parts: Vec<Expression> = vec![];
+        let Token::StringInterpolation(mut bytes, _) = self.curr_token.clone() else {
             unreachable!();
         };
-        Ok(Expression::Function(Rc::new(FunctionExpression {
-            body: stmts,
-            parameters: params,
-            name,
-        })))
+        loop {
+            if !bytes.is_empty() {
+                parts.push(Expression::new(
+                    ExpressionKind::StringLiteral(bytes),
+                    self.curr_span,
+                ));
+            }
+            self.advance_token();
+            parts.push(self.parse_expression(Precedence::Lowest)?);
+            self.advance_token();
+            // NOTE: only the part of the string that resumes after the `}` can follow, a string
+            // literal after the expression is an error rather than taken as the rest of this one.
+            match self.curr_token.clone() {
+                Token::StringInterpolationMiddle(rest, _) => bytes = rest,
+                Token::StringInterpolationEnd(rest, _) => {
+                    if !rest.is_empty() {
+                        parts.push(Expression::new(
+                            ExpressionKind::StringLiteral(rest),
+                            self.curr_span,
+                        ));
+                    }
+                    break;
+                }
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "} to close the interpolated expression",
+                        got: token,
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExpressionKind::InterpolatedString(parts),
+            self.span_from(start),
+        ))
     }
 
     #[allow(unused_variables)]
@@ -282,29 +781,68 @@ impl Parser {
         &mut self,
         precendence: Precedence,
     ) -> Result<Expression, ParseError> {
+        let start = self.curr_span;
         let mut left_expr = match self.curr_token.clone() {
-            Token::True => Expression::BooleanLiteral(true),
-            Token::False => Expression::BooleanLiteral(false),
-            Token::NumberLiteral(val, _) => Expression::NumberLiteral(val),
-            Token::StringLiteral(bytes) => Expression::StringLiteral(bytes.clone()),
-            Token::LParen => self.parse_prefix_grouped_expression()?,
+            Token::True => Expression::new(ExpressionKind::BooleanLiteral(true), start),
+            Token::False => Expression::new(ExpressionKind::BooleanLiteral(false), start),
+            Token::NumberLiteral(val, _) => {
+                Expression::new(ExpressionKind::NumberLiteral(val), start)
+            }
+            Token::StringLiteral(bytes, _) => {
+                Expression::new(ExpressionKind::StringLiteral(bytes.clone()), start)
+            }
+            Token::StringInterpolation(_, _) => {
+                self.parse_nested(Self::parse_interpolated_string)?
+            }
+            Token::LParen if self.allow_arrow_functions && self.is_arrow_function_ahead() => {
+                self.parse_arrow_function()?
+            }
+            Token::LParen
+                if self.allow_arrow_functions
+                    && matches!(self.peek_token, Token::Identifier(..))
+                    && matches!(self.peek_nth(1), Token::EQUAL) =>
+            {
+                self.parse_grouped_expression_or_arrow_function()?
+            }
+            Token::LParen => self.parse_nested(Self::parse_prefix_grouped_expression)?,
+            Token::Identifier(..)
+                if self.allow_arrow_functions && matches!(self.peek_token, Token::ARROW) =>
+            {
+                self.parse_arrow_function()?
+            }
+            Token::LBracket => self.parse_nested(Self::parse_list_expression)?,
+            // NOTE: a `{` that starts a statement is a block, see `parse_statement`.
+            Token::LBrace => self.parse_nested(Self::parse_map_expression)?,
             Token::MINUS | Token::BANG => self.parse_prefix_operator_expression()?,
-            Token::Identifier(ident_bytes) => Expression::Ident(ident_bytes.clone()),
+            Token::PLUSPLUS | Token::MINUSMINUS => self.parse_prefix_update_expression()?,
+            Token::Identifier(symbol, name) => {
+                Expression::new(ExpressionKind::Ident(symbol, name), start)
+            }
             Token::Print => {
                 self.advance_token();
                 let expr = self.parse_expression(precendence.clone())?;
-                Expression::Print(Box::new(expr))
+                Expression::new(ExpressionKind::Print(Box::new(expr)), self.span_from(start))
             }
             Token::Fun => {
                 let fn_expr = self.parse_function_expression()?;
                 fn_expr
             }
-            Token::Nil => Expression::NilLiteral,
+            Token::Nil => Expression::new(ExpressionKind::NilLiteral, start),
+            Token::This => {
+                if self.class_depth == 0 {
+                    return Err(ParseError::OutsideOfClass {
+                        keyword: Token::This,
+                        line: start.line,
+                    });
+                }
+                let symbol = self.interner.borrow_mut().intern(b"this");
+                Expression::new(ExpressionKind::This(symbol), start)
+            }
             t => {
                 return Err(ParseError::ExpectedTokenNotFound {
                     expected: "expression",
                     got: t,
-                    line: self._token_iterator.get_curr_line(),
+                    line: self.curr_span.line,
                 })
             }
         };
@@ -321,6 +859,9 @@ impl Parser {
                 | Token::MINUS
                 | Token::SLASH
                 | Token::STAR
+                | Token::STARSTAR
+                | Token::PERCENT
+                | Token::TILDESLASH
                 | Token::LESS
                 | Token::LESSEQUAL
                 | Token::GREATER
@@ -335,36 +876,178 @@ impl Parser {
                 }
                 Token::LParen => {
                     self.advance_token();
-                    self.parse_call_expression(left_expr)?
+                    self.parse_nested(|parser| parser.parse_call_expression(left_expr))?
+                }
+                Token::DOT => {
+                    self.advance_token();
+                    self.parse_get_expression(left_expr)?
+                }
+                Token::LBracket => {
+                    self.advance_token();
+                    self.parse_nested(|parser| parser.parse_index_expression(left_expr))?
+                }
+                Token::PLUSPLUS | Token::MINUSMINUS => {
+                    self.advance_token();
+                    self.parse_postfix_update_expression(left_expr)?
                 }
-                Token::EQUAL => {
+                Token::QUESTION => {
+                    self.advance_token();
+                    self.parse_conditional_expression(left_expr)?
+                }
+                Token::EQUAL
+                | Token::PLUSEQUAL
+                | Token::MINUSEQUAL
+                | Token::STAREQUAL
+                | Token::SLASHEQUAL => {
                     // NOTE:  assignment is different from other infix operators as this is right associative.
                     self.advance_token();
                     self.parse_assignment_infix_expression(left_expr)?
                 }
-                t => unimplemented!("unimplemented for token: {}", t),
+                // NOTE: only tokens handled above have a precedence above `Lowest`, this keeps a
+                // token that is given one without being handled from crashing the parser.
+                t => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "infix operator",
+                        got: t,
+                        line: self.peek_span.line,
+                    })
+                }
             }
         }
 
         Ok(left_expr)
     }
 
+    fn parse_get_expression(&mut self, left_expr: Expression) -> ParseResult<Expression> {
+        self.advance_token();
+        let property = match &self.curr_token {
+            Token::Identifier(..) => self.curr_token.clone(),
+            token => {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "property name after '.'",
+                    got: token.clone(),
+                    line: self.curr_span.line,
+                })
+            }
+        };
+        let span = self.span_from(left_expr.span);
+        Ok(Expression::new(
+            ExpressionKind::Get {
+                object: Box::new(left_expr),
+                property,
+            },
+            span,
+        ))
+    }
+
+    fn parse_list_expression(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        self.advance_token();
+        let mut elements: Vec<Expression> = vec![];
+        loop {
+            if let Token::RBracket = &self.curr_token {
+                break;
+            }
+            elements.push(self.parse_expression(Precedence::Lowest)?);
+            self.advance_token();
+
+            match &self.curr_token {
+                Token::RBracket => (),
+                Token::COMMA => self.advance_token(),
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "] after list elements",
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExpressionKind::List(elements),
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_map_expression(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        self.advance_token();
+        let mut entries: Vec<(Expression, Expression)> = vec![];
+        loop {
+            if let Token::RBrace = &self.curr_token {
+                break;
+            }
+            let key = self.parse_expression(Precedence::Lowest)?;
+            self.advance_token();
+            let Token::COLON = &self.curr_token else {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: ": after map key",
+                    got: self.curr_token.clone(),
+                    line: self.curr_span.line,
+                });
+            };
+            self.advance_token();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            self.advance_token();
+            entries.push((key, value));
+
+            match &self.curr_token {
+                Token::RBrace => (),
+                Token::COMMA => self.advance_token(),
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "} after map entries",
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExpressionKind::Map(entries),
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_index_expression(&mut self, left_expr: Expression) -> ParseResult<Expression> {
+        self.advance_token();
+        let index = self.parse_expression(Precedence::Lowest)?;
+        let Token::RBracket = self.peek_token else {
+            return Err(ParseError::ExpectedTokenNotFound {
+                expected: "] after index",
+                got: self.peek_token.clone(),
+                line: self.peek_span.line,
+            });
+        };
+        self.advance_token();
+        let span = self.span_from(left_expr.span);
+        Ok(Expression::new(
+            ExpressionKind::Index {
+                object: Box::new(left_expr),
+                index: Box::new(index),
+            },
+            span,
+        ))
+    }
+
     fn parse_assignment_infix_expression(
         &mut self,
         left_expr: Expression,
     ) -> ParseResult<Expression> {
+        let operator = self.curr_token.clone();
         self.advance_token();
-        match &left_expr {
-            Expression::Ident(_) => (),
-            _ => return Err(ParseError::InvalidAssignmentTarget),
-        };
+        ensure_assignment_target(&left_expr, &operator)?;
         let right_expr = self.parse_expression(Precedence::Lowest)?;
 
-        Ok(Expression::InfixExpression {
-            operator: Token::EQUAL,
-            left_expr: Box::new(left_expr),
-            right_expr: Box::new(right_expr),
-        })
+        let span = self.span_from(left_expr.span);
+        Ok(Expression::new(
+            ExpressionKind::InfixExpression {
+                operator,
+                left_expr: Box::new(left_expr),
+                right_expr: Box::new(right_expr),
+            },
+            span,
+        ))
     }
 
     fn ensure_semicolon_at_statement_end(&mut self) -> Result<(), ParseError> {
@@ -377,47 +1060,216 @@ impl Parser {
                 return Err(ParseError::ExpectedTokenNotFound {
                     expected: ";",
                     got: self.peek_token.clone(),
-                    line: self.get_curr_line(),
+                    line: self.peek_span.line,
                 });
             }
         }
     }
 
     fn parse_var_declaration(&mut self) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
         self.advance_token();
-        let ident_bytes = match self.curr_token.clone() {
-            Token::Identifier(iden_bytes) => iden_bytes,
+        let (identifier, name) = match self.curr_token.clone() {
+            Token::Identifier(symbol, name) => (symbol, name),
             token => {
                 return Err(ParseError::ExpectedTokenNotFound {
                     expected: "Identifier",
                     got: token,
-                    line: self.get_curr_line(),
+                    line: self.curr_span.line,
                 })
             }
         };
         match self.peek_token.clone() {
-            Token::SEMICOLON => Ok(Statement::VarDeclaration(VarDeclaration {
-                identifier: ident_bytes,
-                expr: None,
-            })),
+            Token::SEMICOLON => Ok(Statement::new(
+                StatementKind::VarDeclaration(VarDeclaration {
+                    identifier,
+                    name,
+                    expr: None,
+                }),
+                self.span_from(start),
+            )),
             Token::EQUAL => {
                 self.advance_token();
                 self.advance_token();
                 let expr = self.parse_expression(Precedence::Lowest)?;
-                Ok(Statement::VarDeclaration(VarDeclaration {
-                    identifier: ident_bytes,
-                    expr: Some(expr),
-                }))
+                Ok(Statement::new(
+                    StatementKind::VarDeclaration(VarDeclaration {
+                        identifier,
+                        name,
+                        expr: Some(expr),
+                    }),
+                    self.span_from(start),
+                ))
             }
             token => Err(ParseError::ExpectedTokenNotFound {
                 expected: "expression",
                 got: token,
-                line: self.get_curr_line(),
+                line: self.peek_span.line,
             }),
         }
     }
 
+    fn parse_class_declaration(&mut self) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
+        self.advance_token();
+        let name = match &self.curr_token {
+            Token::Identifier(..) => self.curr_token.clone(),
+            token => {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "class name",
+                    got: token.clone(),
+                    line: self.curr_span.line,
+                })
+            }
+        };
+        self.advance_token();
+        if !matches!(self.curr_token, Token::LBrace) {
+            return Err(ParseError::ExpectedTokenNotFound {
+                expected: "{ before class body",
+                got: self.curr_token.clone(),
+                line: self.curr_span.line,
+            });
+        }
+        self.advance_token();
+
+        self.class_depth += 1;
+        let methods = self.parse_methods();
+        self.class_depth -= 1;
+        Ok(Statement::new(
+            StatementKind::Class(ClassDeclaration {
+                name,
+                methods: methods?,
+            }),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses the methods of a class up to its `}`, which is left as the current token.
+    fn parse_methods(&mut self) -> ParseResult<Vec<Rc<FunctionExpression>>> {
+        let mut methods = vec![];
+        loop {
+            match &self.curr_token {
+                Token::RBrace => break,
+                Token::Identifier(_, name) => {
+                    let is_initializer = name.as_ref() == b"init";
+                    let method = self.parse_function(self.curr_span, is_initializer)?;
+                    let ExpressionKind::Function(method) = method.kind else {
+                        unreachable!();
+                    };
+                    methods.push(method);
+                    self.advance_token();
+                }
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "method name or } after class body",
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+        }
+        Ok(methods)
+    }
+
+    /// Parses an enum declaration, leaving its `}` as the current token.
+    fn parse_enum_declaration(&mut self) -> ParseResult<Statement> {
+        let start = self.curr_span;
+        self.advance_token();
+        let name = match &self.curr_token {
+            Token::Identifier(..) => self.curr_token.clone(),
+            token => {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "enum name",
+                    got: token.clone(),
+                    line: self.curr_span.line,
+                })
+            }
+        };
+        self.advance_token();
+        if !matches!(self.curr_token, Token::LBrace) {
+            return Err(ParseError::ExpectedTokenNotFound {
+                expected: "{ before enum variants",
+                got: self.curr_token.clone(),
+                line: self.curr_span.line,
+            });
+        }
+        self.advance_token();
+
+        let mut variants = vec![];
+        while !matches!(self.curr_token, Token::RBrace) {
+            let name = match &self.curr_token {
+                Token::Identifier(..) => self.curr_token.clone(),
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "variant name or } after enum variants",
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            };
+            self.advance_token();
+            let mut fields = vec![];
+            if let Token::LParen = self.curr_token {
+                fields = self.parse_identifier_list("field name")?;
+            }
+            variants.push(EnumVariant { name, fields });
+
+            match &self.curr_token {
+                Token::RBrace => (),
+                Token::COMMA => self.advance_token(),
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "} after enum variants",
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+        }
+        Ok(Statement::new(
+            StatementKind::Enum(EnumDeclaration { name, variants }),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses `(a, b, ...)` at the current token, leaving the token after the `)` as the current
+    /// one.
+    fn parse_identifier_list(&mut self, expected: &'static str) -> ParseResult<Vec<Token>> {
+        self.advance_token();
+        let mut identifiers = vec![];
+        loop {
+            if let Token::RParen = self.curr_token {
+                self.advance_token();
+                break;
+            }
+            match &self.curr_token {
+                Token::Identifier(..) => identifiers.push(self.curr_token.clone()),
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected,
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+            self.advance_token();
+            match &self.curr_token {
+                Token::RParen => (),
+                Token::COMMA => self.advance_token(),
+                token => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: ") after the list",
+                        got: token.clone(),
+                        line: self.curr_span.line,
+                    })
+                }
+            }
+        }
+        Ok(identifiers)
+    }
+
     fn parse_if_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
         self.advance_token();
         let expr = self.parse_expression(Precedence::Lowest)?;
         self.advance_token();
@@ -427,19 +1279,159 @@ impl Parser {
             self.advance_token();
             else_block = Some(self.parse_statement()?);
         }
-        Ok(Statement::IfStatement(Box::new(IfStatement {
-            else_block,
-            expr,
-            if_block,
-        })))
+        Ok(Statement::new(
+            StatementKind::IfStatement(Box::new(IfStatement {
+                else_block,
+                expr,
+                if_block,
+            })),
+            self.span_until_prev(start),
+        ))
     }
 
+    /// Parses a match statement, leaving the token after its `}` as the current one.
+    fn parse_match_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.curr_span;
+        self.advance_token();
+        let subject = self.parse_expression(Precedence::Lowest)?;
+        self.advance_token();
+        if !matches!(self.curr_token, Token::LBrace) {
+            return Err(ParseError::ExpectedTokenNotFound {
+                expected: "{ before match arms",
+                got: self.curr_token.clone(),
+                line: self.curr_span.line,
+            });
+        }
+        self.advance_token();
+
+        let mut arms = vec![];
+        while !matches!(self.curr_token, Token::RBrace) {
+            let mut patterns = vec![self.parse_pattern()?];
+            while let Token::PIPE = self.curr_token {
+                self.advance_token();
+                patterns.push(self.parse_pattern()?);
+            }
+            let mut guard = None;
+            if let Token::If = self.curr_token {
+                self.advance_token();
+                let enclosing_allow_arrow_functions =
+                    std::mem::replace(&mut self.allow_arrow_functions, false);
+                let expr = self.parse_expression(Precedence::Lowest);
+                self.allow_arrow_functions = enclosing_allow_arrow_functions;
+                guard = Some(expr?);
+                self.advance_token();
+            }
+            if !matches!(self.curr_token, Token::ARROW) {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "=> after match pattern",
+                    got: self.curr_token.clone(),
+                    line: self.curr_span.line,
+                });
+            }
+            self.advance_token();
+            let body = self.parse_statement()?;
+            arms.push(MatchArm {
+                patterns,
+                guard,
+                body,
+            });
+        }
+        self.advance_token();
+        Ok(Statement::new(
+            StatementKind::Match(Box::new(MatchStatement { subject, arms })),
+            self.span_until_prev(start),
+        ))
+    }
+
+    /// Parses a single pattern of a match arm, leaving the token after it as the current one.
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        if let Token::Identifier(_, name) = &self.curr_token {
+            if name.as_ref() == b"_" {
+                self.advance_token();
+                return Ok(Pattern::Wildcard);
+            }
+            let name = self.curr_token.clone();
+            self.advance_token();
+            let mut bindings = vec![];
+            if let Token::LParen = self.curr_token {
+                bindings = self.parse_identifier_list("binding name")?;
+            }
+            return Ok(Pattern::Variant { name, bindings });
+        }
+        let literal = self.parse_pattern_literal()?;
+        self.advance_token();
+        let ExpressionKind::NumberLiteral(start) = literal.kind else {
+            return Ok(Pattern::Literal(literal));
+        };
+        if !matches!(self.curr_token, Token::DOTDOT) {
+            return Ok(Pattern::Literal(literal));
+        }
+        self.advance_token();
+        let end = match self.parse_pattern_literal()?.kind {
+            ExpressionKind::NumberLiteral(end) => end,
+            _ => {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "number after '..'",
+                    got: self.curr_token.clone(),
+                    line: self.curr_span.line,
+                })
+            }
+        };
+        self.advance_token();
+        Ok(Pattern::Range { start, end })
+    }
+
+    fn parse_pattern_literal(&mut self) -> ParseResult<Expression> {
+        let start = self.curr_span;
+        let kind = match self.curr_token.clone() {
+            Token::NumberLiteral(val, _) => ExpressionKind::NumberLiteral(val),
+            Token::MINUS => match self.peek_token {
+                Token::NumberLiteral(val, _) => {
+                    self.advance_token();
+                    ExpressionKind::NumberLiteral(-val)
+                }
+                _ => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "number after '-' in pattern",
+                        got: self.peek_token.clone(),
+                        line: self.peek_span.line,
+                    })
+                }
+            },
+            Token::StringLiteral(bytes, _) => ExpressionKind::StringLiteral(bytes),
+            Token::True => ExpressionKind::BooleanLiteral(true),
+            Token::False => ExpressionKind::BooleanLiteral(false),
+            Token::Nil => ExpressionKind::NilLiteral,
+            token => {
+                return Err(ParseError::ExpectedTokenNotFound {
+                    expected: "pattern",
+                    got: token,
+                    line: self.curr_span.line,
+                })
+            }
+        };
+        Ok(Expression::new(kind, self.span_from(start)))
+    }
+
+    /// `return value;`, or `return;` which returns nil.
     fn parse_return_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
+        if let Token::SEMICOLON = self.peek_token {
+            let nil = Expression::new(ExpressionKind::NilLiteral, start);
+            return Ok(Statement::new(StatementKind::Return(nil), start));
+        }
+        if self.in_initializer {
+            return Err(ParseError::ReturnValueFromInitializer { line: start.line });
+        }
         self.advance_token();
         let expr = self.parse_expression(Precedence::Lowest)?;
-        return Ok(Statement::Return(expr));
+        Ok(Statement::new(
+            StatementKind::Return(expr),
+            self.span_from(start),
+        ))
     }
     fn parse_while_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
         self.advance_token();
         let expr: Option<Expression>;
         if let Token::LBrace = &self.curr_token {
@@ -449,18 +1441,23 @@ impl Parser {
             self.advance_token();
         }
 
-        let stmt = self.parse_statement()?;
-        Ok(Statement::WhileLoop(WhileLoop {
-            expr,
-            block: Box::new(stmt),
-        }))
+        let stmt = self.parse_loop_body()?;
+        Ok(Statement::new(
+            StatementKind::WhileLoop(WhileLoop {
+                expr,
+                block: Box::new(stmt),
+                increment: None,
+            }),
+            self.span_until_prev(start),
+        ))
     }
 
     fn parse_for_statement_and_desugar_it(&mut self) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
         self.advance_token();
         let var_declaration: Option<Statement>;
         let conditional_expr: Option<Expression>;
-        let incr_stmt: Option<Statement>;
+        let increment: Option<Expression>;
         let block_body: Statement;
         if let Token::SEMICOLON = self.curr_token {
             self.advance_token();
@@ -478,12 +1475,12 @@ impl Parser {
         }
         self.advance_token();
         if let Token::LBrace = self.curr_token {
-            block_body = self.parse_statement()?;
-            incr_stmt = None;
+            block_body = self.parse_loop_body()?;
+            increment = None;
         } else {
-            incr_stmt = Some(self.parse_single_statement_without_semicolon()?);
+            increment = Some(self.parse_expression(Precedence::Lowest)?);
             self.advance_token();
-            block_body = self.parse_statement()?;
+            block_body = self.parse_loop_body()?;
         }
 
         let mut final_block_stmts = Vec::<Statement>::new();
@@ -491,46 +1488,86 @@ impl Parser {
             final_block_stmts.push(v);
         }
 
-        let mut while_block_body: Vec<Statement> = vec![block_body];
-
-        if let Some(v) = incr_stmt {
-            while_block_body.push(v);
-        }
-        let while_loop = Statement::WhileLoop(WhileLoop {
-            expr: conditional_expr,
-            block: Box::new(Statement::Block(while_block_body)),
-        });
+        // NOTE: the statements the loop is desugared into all get the span of the whole loop.
+        let span = self.span_until_prev(start);
+        let while_loop = Statement::new(
+            StatementKind::WhileLoop(WhileLoop {
+                expr: conditional_expr,
+                block: Box::new(block_body),
+                increment,
+            }),
+            span,
+        );
         final_block_stmts.push(while_loop);
-        Ok(Statement::Block(final_block_stmts))
+        Ok(Statement::new(
+            StatementKind::Block(final_block_stmts),
+            span,
+        ))
+    }
+
+    fn parse_loop_body(&mut self) -> ParseResult<Statement> {
+        self.loop_depth += 1;
+        let body = self.parse_statement();
+        self.loop_depth -= 1;
+        body
     }
 
     fn parse_single_statement_without_semicolon(&mut self) -> ParseResult<Statement> {
+        let start = self.curr_span;
         let stmt = match &self.curr_token {
             Token::Print => {
                 self.advance_token();
                 let expr = self.parse_expression(Precedence::Lowest)?;
-                Statement::Print(expr)
+                Statement::new(StatementKind::Print(expr), self.span_from(start))
             }
             Token::Var => self.parse_var_declaration()?,
+            Token::Class => self.parse_class_declaration()?,
+            Token::Enum => self.parse_enum_declaration()?,
             Token::If => return self.parse_if_statement(),
+            Token::Match => return self.parse_match_statement(),
             Token::While => return self.parse_while_statement(),
             Token::For => self.parse_for_statement_and_desugar_it()?,
             Token::Return => self.parse_return_statement()?,
-            _ => Statement::Expression(self.parse_expression(Precedence::Lowest)?),
+            keyword @ (Token::Break | Token::Continue) => {
+                if self.loop_depth == 0 {
+                    return Err(ParseError::OutsideOfLoop {
+                        keyword: keyword.clone(),
+                        line: start.line,
+                    });
+                }
+                match keyword {
+                    Token::Break => Statement::new(StatementKind::Break, start),
+                    _ => Statement::new(StatementKind::Continue, start),
+                }
+            }
+            _ => {
+                let expr = self.parse_expression(Precedence::Lowest)?;
+                Statement::new(StatementKind::Expression(expr), self.span_from(start))
+            }
         };
         Ok(stmt)
     }
 
     fn parse_single_statement(&mut self) -> Result<Statement, ParseError> {
+        let starts_with_fun = matches!(self.curr_token, Token::Fun);
         let stmt = self.parse_single_statement_without_semicolon()?;
         // println!("{stmt:?}");
-        match &stmt {
-            Statement::IfStatement(_) | Statement::WhileLoop(_) | Statement::Block(_) => {
-                return Ok(stmt)
+        match &stmt.kind {
+            StatementKind::IfStatement(_)
+            | StatementKind::WhileLoop(_)
+            | StatementKind::Match(_)
+            | StatementKind::Block(_) => Ok(stmt),
+            // NOTE: unlike arrow functions, `fun` declarations don't end with a `;`.
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Function(_),
+                ..
+            }) if starts_with_fun => {
+                self.advance_token();
+                Ok(stmt)
             }
-            Statement::Expression(Expression::Function(_)) => {
+            StatementKind::Class(_) | StatementKind::Enum(_) => {
                 self.advance_token();
-                return Ok(stmt);
+                Ok(stmt)
             }
             _ => {
                 self.ensure_semicolon_at_statement_end()?;
@@ -544,19 +1581,32 @@ impl Parser {
         &mut self,
         is_block_part_of_expression: bool,
     ) -> Result<Statement, ParseError> {
+        let start = self.curr_span;
         self.advance_token();
         let mut stms: Vec<Statement> = vec![];
-        loop {
-            if let Token::RBrace = self.curr_token {
-                if !is_block_part_of_expression {
-                    self.advance_token();
+        let span = loop {
+            match self.curr_token {
+                Token::RBrace => {
+                    let span = self.span_from(start);
+                    if !is_block_part_of_expression {
+                        self.advance_token();
+                    }
+                    break span;
                 }
-                break;
+                Token::EOF => {
+                    return Err(ParseError::ExpectedTokenNotFound {
+                        expected: "}",
+                        got: Token::EOF,
+                        line: self.curr_span.line,
+                    })
+                }
+                _ => (),
             }
-            let stmt = self.parse_statement()?;
-            stms.push(stmt);
-        }
-        Ok(Statement::Block(stms))
+            if let Some(stmt) = self.parse_declaration() {
+                stms.push(stmt);
+            }
+        };
+        Ok(Statement::new(StatementKind::Block(stms), span))
     }
 
     fn parse_statement(&mut self) -> Result<Statement, ParseError> {
@@ -567,15 +1617,142 @@ impl Parser {
         Ok(stmt)
     }
 
+    /// Parses a source holding a single expression, as used by the `parse` and `evaluate`
+    /// commands.
+    pub(crate) fn parse_standalone_expression(&mut self) -> ParseResult<Expression> {
+        let expr = self
+            .parse_expression(Precedence::Lowest)
+            .map_err(|e| self.prefer_lexical_errors(e))?;
+        self.ensure_no_lexical_errors()?;
+        // NOTE: a function expression recovers from the errors in its body.
+        let mut errors = std::mem::take(&mut self.parse_errors);
+        match errors.len() {
+            0 => Ok(expr),
+            1 => Err(errors.remove(0)),
+            _ => Err(ParseError::Multiple(errors)),
+        }
+    }
+
+    /// Parses the whole program, stopping at the first error. See `parse_program_with_errors`
+    /// for the statements that did parse and every error.
     pub(crate) fn parse_program(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let (statements, mut errors) = self.parse_program_with_errors();
+        match errors.len() {
+            0 => Ok(statements),
+            1 => Err(errors.remove(0)),
+            _ => Err(ParseError::Multiple(errors)),
+        }
+    }
+
+    /// Parses the whole program, recovering from parse errors by skipping to the start of the
+    /// next statement. Returns the statements that parsed along with every error. Lexical errors
+    /// are returned instead of the parse errors when there are any, as those most likely are a
+    /// consequence of them.
+    pub(crate) fn parse_program_with_errors(&mut self) -> (Vec<Statement>, Vec<ParseError>) {
         let mut statements = vec![];
-        loop {
-            if let Token::EOF = self.curr_token {
-                break;
+        while !matches!(self.curr_token, Token::EOF) {
+            if let Some(stmt) = self.parse_declaration() {
+                statements.push(stmt);
+            }
+        }
+        let errors = match self.ensure_no_lexical_errors() {
+            Err(e) => vec![e],
+            Ok(()) => std::mem::take(&mut self.parse_errors),
+        };
+        (statements, errors)
+    }
+
+    /// Parses a statement, on error the error is recorded and the parser synchronizes to the
+    /// next statement.
+    fn parse_declaration(&mut self) -> Option<Statement> {
+        match self.parse_statement() {
+            Ok(stmt) => Some(stmt),
+            Err(e) => {
+                self.parse_errors.push(e);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    /// Skips tokens until the start of what is likely the next statement, i.e. past a `;` or up
+    /// to a keyword that starts a statement. Always skips at least one token, so that parsing
+    /// makes progress.
+    fn synchronize(&mut self) {
+        while !matches!(self.curr_token, Token::EOF) {
+            let was_semicolon = matches!(self.curr_token, Token::SEMICOLON);
+            self.advance_token();
+            if was_semicolon {
+                return;
+            }
+            match self.curr_token {
+                Token::Class
+                | Token::Enum
+                | Token::Fun
+                | Token::Var
+                | Token::For
+                | Token::If
+                | Token::Match
+                | Token::While
+                | Token::Print
+                | Token::Return => return,
+                _ => (),
+            }
+        }
+    }
+
+    fn ensure_no_lexical_errors(&mut self) -> ParseResult<()> {
+        if self.lexical_errors.is_empty() {
+            return Ok(());
+        }
+        Err(ParseError::LexicalErrors(std::mem::take(
+            &mut self.lexical_errors,
+        )))
+    }
+
+    /// Scans the rest of the source after a parse error so that every lexical error gets
+    /// reported. If there are any, they are returned instead of the parse error, which most
+    /// likely is a consequence of them.
+    fn prefer_lexical_errors(&mut self, parse_error: ParseError) -> ParseError {
+        while !matches!(self.peek_token, Token::EOF) {
+            self.advance_token();
+        }
+        match self.ensure_no_lexical_errors() {
+            Err(e) => e,
+            Ok(()) => parse_error,
+        }
+    }
+}
+
+/// Variables, properties and indexes can be assigned to, by `=`, `+=`, ... and `++`/`--`.
+fn ensure_assignment_target(target: &Expression, operator: &Token) -> ParseResult<()> {
+    match &target.kind {
+        ExpressionKind::Ident(..) | ExpressionKind::Get { .. } | ExpressionKind::Index { .. } => {
+            Ok(())
+        }
+        _ => Err(ParseError::InvalidAssignmentTarget {
+            operator: operator.clone(),
+        }),
+    }
+}
+
+/// Next token of the iterator, collecting the lexical errors on the way so that scanning goes on
+/// past them.
+fn next_token(
+    token_iterator: &mut TokenIterator,
+    lexical_errors: &mut Vec<LexicalError>,
+) -> SpannedToken {
+    loop {
+        match token_iterator.next() {
+            Some(Ok(token)) => return token,
+            Some(Err(e)) => lexical_errors.push(e),
+            None => {
+                return SpannedToken {
+                    token: Token::EOF,
+                    span: Span::default(),
+                    lexeme: Bytes::new(),
+                }
             }
-            let stmt = self.parse_statement()?;
-            statements.push(stmt);
         }
-        Ok(statements)
     }
 }
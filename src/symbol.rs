@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+};
+
+use bytes::Bytes;
+
+/// An interned identifier. Two identifiers with the same name always get the same symbol from an
+/// `Interner`, so comparing and hashing them is comparing and hashing a single integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Symbol(u32);
+
+/// Maps identifier names to `Symbol`s and back. The scanner interns every identifier it scans,
+/// and the interpreter shares the same interner through the parser.
+#[derive(Default)]
+pub(crate) struct Interner {
+    symbols: HashMap<Bytes, Symbol>,
+    names: Vec<Bytes>,
+}
+
+impl Interner {
+    pub(crate) fn intern(&mut self, name: &[u8]) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+        // NOTE: the name is copied, a slice of the source would keep the whole source (or the
+        // whole chunk of a streamed source) alive for as long as the interner.
+        let name = Bytes::copy_from_slice(name);
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.clone());
+        self.symbols.insert(name, symbol);
+        symbol
+    }
+
+    /// The name `symbol` was interned from.
+    pub(crate) fn resolve(&self, symbol: Symbol) -> &Bytes {
+        &self.names[symbol.0 as usize]
+    }
+}
+
+/// Symbols are already small unique integers, so hashing them doesn't need SipHash. Multiplying
+/// by a large odd constant spreads consecutive symbols over the buckets.
+#[derive(Default)]
+pub(crate) struct SymbolHasher(u64);
+
+impl Hasher for SymbolHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0.rotate_left(8) ^ u64::from(*byte)).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.0 = u64::from(value).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+}
+
+pub(crate) type SymbolMap<V> = HashMap<Symbol, V, BuildHasherDefault<SymbolHasher>>;
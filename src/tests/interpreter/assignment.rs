@@ -3,6 +3,7 @@ use core::panic;
 use crate::{
     interpreter::{EvaluationError, Interpreter},
     parser::ParseError,
+    tests::test_positive_test,
 };
 
 #[test]
@@ -39,7 +40,7 @@ fn test_infix_operator() {
     let mut interpreter = Interpreter::from_source(source, writer).unwrap();
     match interpreter.evaluate_program() {
         Err(EvaluationError::ParseError(parse_err)) => match parse_err {
-            ParseError::InvalidAssignmentTarget => (),
+            ParseError::InvalidAssignmentTarget { .. } => (),
             _ => panic!("expected InvalidAssignmentTarget"),
         },
         _ => panic!("expected error"),
@@ -53,7 +54,7 @@ fn test_grouping() {
     let mut interpreter = Interpreter::from_source(source, writer).unwrap();
     match interpreter.evaluate_program() {
         Err(EvaluationError::ParseError(parse_err)) => match parse_err {
-            ParseError::InvalidAssignmentTarget => (),
+            ParseError::InvalidAssignmentTarget { .. } => (),
             _ => panic!("expected InvalidAssignmentTarget"),
         },
         _ => panic!("expected error"),
@@ -67,7 +68,7 @@ fn test_prefix_operator() {
     let mut interpreter = Interpreter::from_source(source, writer).unwrap();
     match interpreter.evaluate_program() {
         Err(EvaluationError::ParseError(parse_err)) => match parse_err {
-            ParseError::InvalidAssignmentTarget => (),
+            ParseError::InvalidAssignmentTarget { .. } => (),
             _ => panic!("expected InvalidAssignmentTarget"),
         },
         _ => panic!("expected error"),
@@ -94,3 +95,76 @@ fn test_undefined() {
         t => panic!("expected error, but got: {t:?}"),
     };
 }
+
+#[test]
+fn compound_assignment() {
+    let source = r#"
+var a = 10;
+a += 5;
+a -= 3;
+a *= 2;
+a /= 4;
+print a;
+var s = "ab";
+s += "c";
+print s;
+class Box {}
+var box = Box();
+box.value = 1;
+print box.value += 2;
+"#
+    .to_string();
+    test_positive_test(source, "6\nabc\n3\n");
+}
+
+#[test]
+fn increment_and_decrement() {
+    let source = r#"
+var i = 0;
+print i++;
+print i;
+print ++i;
+print i--;
+print --i;
+var xs = [1, 2];
+xs[1]++;
+--xs[0];
+print xs;
+"#
+    .to_string();
+    test_positive_test(source, "0\n1\n2\n2\n0\n[0, 3]\n");
+}
+
+#[test]
+fn compound_assignment_evaluates_its_target_once() {
+    let source = r#"
+var calls = 0;
+fun index() { calls++; return 0; }
+var xs = [10];
+xs[index()] += 1;
+xs[index()]++;
+print xs;
+print calls;
+"#
+    .to_string();
+    test_positive_test(source, "[12]\n2\n");
+}
+
+#[test]
+fn invalid_compound_assignment_target() {
+    for source in ["1 += 2;", "(a) -= 1;", "a + b *= 3;", "++1;", "f()--;"] {
+        let mut interpreter = Interpreter::from_source(source.to_string(), vec![]).unwrap();
+        match interpreter.evaluate_program() {
+            Err(EvaluationError::ParseError(ParseError::InvalidAssignmentTarget { .. })) => (),
+            t => panic!("expected InvalidAssignmentTarget for {source}, but got: {t:?}"),
+        }
+    }
+    let mut interpreter = Interpreter::from_source("1 += 2;".to_string(), vec![]).unwrap();
+    match interpreter.evaluate_program() {
+        Err(e) => assert_eq!(
+            format!("{e:?}"),
+            "Error at '+=': Invalid assignment target."
+        ),
+        t => panic!("expected error, but got: {t:?}"),
+    }
+}
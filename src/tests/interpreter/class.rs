@@ -0,0 +1,105 @@
+use crate::{
+    interpreter::{EvaluationError, Interpreter},
+    tests::{test_positive_test, test_runtime_error},
+};
+
+#[test]
+fn fields_and_methods() {
+    let source = r#"
+class Counter {
+  init(start) {
+    this.count = start;
+  }
+  increment() {
+    this.count = this.count + 1;
+    return this;
+  }
+}
+var counter = Counter(1);
+counter.increment().increment();
+print counter.count;
+print counter;
+print Counter;
+"#
+    .to_string();
+    test_positive_test(source, "3\n<Counter instance>\n<class Counter>\n");
+}
+
+#[test]
+fn bound_methods_keep_their_instance() {
+    let source = r#"
+class Person {
+  init(name) { this.name = name; }
+  greet() { print "Hi, " + this.name; }
+}
+var greet = Person("Ada").greet;
+var bob = Person("Bob");
+bob.greet = greet;
+bob.greet();
+print bob.init("Eve") == bob;
+print bob.name;
+"#
+    .to_string();
+    test_positive_test(source, "Hi, Ada\ntrue\nEve\n");
+}
+
+#[test]
+fn undefined_property() {
+    let source = "class Foo {}\nvar foo = Foo();\nprint foo.bar;".to_string();
+    test_runtime_error(source, "Undefined property 'bar'.\n[line 3]");
+}
+
+#[test]
+fn initializer_returns_and_this() {
+    let source = r#"
+class Point {
+  init(x) {
+    this.x = x;
+    if x < 0 return;
+    this.y = fun () { return this.x * 2; };
+  }
+}
+print Point(-1).x;
+print Point(2).y();
+"#
+    .to_string();
+    test_positive_test(source, "-1\n4\n");
+
+    let cases = [
+        (
+            "class A {\n  init() { return 1; }\n}",
+            "[line 2] Error at 'return': Can't return a value from an initializer.",
+        ),
+        (
+            "fun f() {\n  print this;\n}",
+            "[line 2] Error at 'this': Can't use 'this' outside of a class.",
+        ),
+    ];
+    for (source, expected) in cases {
+        match Interpreter::from_source(source.to_string(), vec![])
+            .unwrap()
+            .evaluate_program()
+        {
+            Err(EvaluationError::ParseError(e)) => {
+                assert_eq!(format!("{e:?}").lines().next(), Some(expected))
+            }
+            got => panic!("expected parse error for {source}, got {got:?}"),
+        }
+    }
+}
+
+#[test]
+fn classes_are_equal_only_to_themselves() {
+    let source = r#"
+class A {}
+class B {}
+var alias = A;
+print A == A;
+print A == alias;
+print A == B;
+print A != B;
+print A == A();
+"#
+    .to_string();
+    test_positive_test(source, "true\ntrue\nfalse\ntrue\nfalse\n");
+}
@@ -0,0 +1,99 @@
+use crate::tests::{test_positive_test, test_runtime_error};
+
+#[test]
+fn enum_values() {
+    let source = r#"
+enum Shape { Circle(r), Rect(w, h), Empty, }
+print Circle;
+print Circle(2);
+print Rect(1, "two");
+print Empty;
+print Circle(2) == Circle(2);
+print Circle(2) == Circle(3);
+print Rect(1, 2) != Circle(1);
+print Empty == Empty;
+print Circle(...[4]);
+var make = Circle;
+print make == Circle;
+print Circle == Rect;
+print Circle != Circle;
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "<constructor Circle>\nCircle(2)\nRect(1, \"two\")\nEmpty\ntrue\nfalse\ntrue\ntrue\nCircle(4)\ntrue\nfalse\nfalse\n",
+    );
+}
+
+#[test]
+fn destructuring_match() {
+    let source = r#"
+enum Shape { Circle(r), Rect(w, h), Empty }
+fun area(shape) {
+  match shape {
+    Circle(r) => return 3 * r * r;
+    Rect(w, h) if w == h => return "square ${w * h}";
+    Rect(w, _) | Circle(w) => return w;
+    Empty => return 0;
+  }
+}
+print area(Circle(2));
+print area(Rect(3, 3));
+print area(Rect(2, 5));
+print area(Empty);
+
+var w = "outer";
+match Rect(1, 2) { Rect(w, h) => print w + h; }
+print w;
+
+enum Result { Ok(value), Err(message) }
+fun divide(a, b) {
+  if b == 0 return Err("division by zero");
+  return Ok(a / b);
+}
+for var i = 0; i < 2; i++ {
+  match divide(6, i) {
+    Ok(value) => print value;
+    Err(message) => print "error: ${message}";
+  }
+}
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "12\nsquare 9\n2\n0\n3\nouter\nerror: division by zero\n6\n",
+    );
+}
+
+#[test]
+fn invalid_enum_use() {
+    let cases = [
+        (
+            "enum E { A(x) } A(1, 2);",
+            "Expected 1 arguments but got 2.\n[line 1]",
+        ),
+        (
+            "enum E { A(x) } A(x: 1);",
+            "Unknown argument 'x'.\n[line 1]",
+        ),
+        (
+            "var B = 1;\nmatch 1 { B => print 1; }",
+            "'B' is not an enum variant.\n[line 2]",
+        ),
+        (
+            "enum E { A(x) } match A(1) { A(x, y) => print x; }",
+            "Pattern 'A' has 2 bindings but the variant has 1 fields.\n[line 1]",
+        ),
+        (
+            "enum Color { Red, Green }\nenum Light { Red, Off }",
+            "Enum variant 'Red' is already declared.\n[line 2]",
+        ),
+        (
+            "enum Shape { Circle(r), Circle(x, y) }",
+            "Enum variant 'Circle' is already declared.\n[line 1]",
+        ),
+    ];
+    for (source, expected) in cases {
+        test_runtime_error(source.to_string(), expected);
+    }
+}
@@ -2,7 +2,7 @@ use core::panic;
 
 use crate::{
     interpreter::{EvaluationError, Interpreter},
-    tests::test_positive_test,
+    tests::{test_positive_test, test_runtime_error},
 };
 
 #[test]
@@ -64,15 +64,184 @@ fn recursion() {
 #[test]
 fn extra_arguments() {
     let source = include_str!("../../../lox-test/function/extra_arguments.lox").to_string();
-    let writer = vec![];
-    let mut interpreter = Interpreter::from_source(source, writer).unwrap();
-    match interpreter.evaluate_program() {
-        Ok(_) => panic!("was supposed to return error but instead got success."),
-        Err(err) => match err {
-            EvaluationError::Runtime(s) => {
-                assert_eq!(s.as_str(), "Expected 2 arguments but got 4.")
-            }
-            err => panic!("expected runtime error, but got {err:?}"),
-        },
-    };
+    test_runtime_error(source, "Expected 2 arguments but got 4.\n[line 6]");
+}
+
+#[test]
+fn arrow_functions() {
+    let source = r#"
+var add = (a, b) => a + b;
+var square = x => x * x;
+var answer = () => 42;
+fun apply(f, x) { return f(x); }
+print add(1, 2);
+print apply(square, 3);
+print answer();
+print ((x) => x + 1)(1) * 10;
+print (1 + 2) * 3;
+var log = message => {
+  print "log: " + message;
+  return nil;
+};
+log("hi");
+print add;
+"#
+    .to_string();
+    test_positive_test(source, "3\n9\n42\n20\n9\nlog: hi\n<fn>\n");
+}
+
+#[test]
+fn arrow_functions_and_grouped_assignments() {
+    let source = r#"
+var a = 1;
+print (a = 2);
+print a;
+var f = (a = 3) => a * 2;
+print f();
+print f(5);
+var g = (a = 1, b = a + 1, ...rest) => [a + b, rest];
+print g();
+print g(2, 0, 4);
+var h = (x) => (y) => x + y;
+print h(1)(2);
+print ((((1 + 2))));
+"#
+    .to_string();
+    test_positive_test(source, "2\n2\n6\n10\n[3, []]\n[2, [4]]\n3\n3\n");
+}
+
+#[test]
+fn arrow_functions_capture_their_environment() {
+    let source = r#"
+fun counter() {
+  var count = 0;
+  return () => count = count + 1;
+}
+var next = counter();
+next();
+print next();
+var adders = [];
+for var i = 1; i < 3; i = i + 1 {
+  var n = i;
+  adders = [x => x + n, adders];
+}
+print adders[0](10);
+"#
+    .to_string();
+    test_positive_test(source, "2\n12\n");
+}
+
+#[test]
+fn default_parameters_and_named_arguments() {
+    let source = r#"
+var unit = "cm";
+fun area(width, height = width, label = unit) {
+  return "${label}: ${width * height}";
+}
+print area(2);
+print area(2, 3);
+print area(2, label: "m");
+print area(height: 4, width: 1);
+unit = "mm";
+print area(5);
+var scale = (x, by = 2) => x * by;
+print scale(3) + scale(3, by: 10);
+"#
+    .to_string();
+    test_positive_test(source, "cm: 4\ncm: 6\nm: 4\ncm: 4\nmm: 25\n36\n");
+}
+
+#[test]
+fn invalid_arguments() {
+    let cases = [
+        (
+            "fun f(a, b = 1) {}\nf(c: 1);",
+            "Unknown argument 'c'.\n[line 2]",
+        ),
+        (
+            "fun f(a, b = 1) {}\nf(1, a: 2);",
+            "Argument 'a' is given more than once.\n[line 2]",
+        ),
+        (
+            "fun f(a, b = 1) {}\nf(b: 2, b: 3);",
+            "Argument 'b' is given more than once.\n[line 2]",
+        ),
+        (
+            "fun f(a, b = 1) {}\nf(b: 2);",
+            "Missing argument 'a'.\n[line 2]",
+        ),
+        (
+            "fun f(a, b = 1) {}\nf(1, 2, 3);",
+            "Expected 2 arguments but got 3.\n[line 2]",
+        ),
+        (
+            "fun f(a, b) {}\nf(1);",
+            "Expected 2 arguments but got 1.\n[line 2]",
+        ),
+    ];
+    for (source, expected) in cases {
+        test_runtime_error(source.to_string(), expected);
+    }
+
+    let source = "fun f(a, b) {}\nf(a: 1, 2);".to_string();
+    match Interpreter::from_source(source, vec![])
+        .unwrap()
+        .evaluate_program()
+    {
+        Err(EvaluationError::ParseError(e)) => assert_eq!(
+            format!("{e:?}"),
+            "[line 2] Error: Positional arguments must come before named arguments."
+        ),
+        got => panic!("expected parse error, but got: {got:?}"),
+    }
+}
+
+#[test]
+fn rest_parameters_and_spread_arguments() {
+    let source = r#"
+fun log(level, ...parts) {
+  print "${level}: ${parts}";
+}
+log("info");
+log("warn", 1, "two", [3]);
+var args = ["a", "b"];
+fun forward(...xs) { log("debug", ...xs, "c"); }
+forward(...args);
+var sum = (a, b = 10, ...more) => a + b + more[0];
+print sum(...[1, 2, 3]);
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "info: []\nwarn: [1, \"two\", [3]]\ndebug: [\"a\", \"b\", \"c\"]\n6\n",
+    );
+}
+
+#[test]
+fn invalid_spread() {
+    let cases = [
+        (
+            "fun f(a) {}\nf(...[1, 2]);",
+            "Expected 1 arguments but got 2.\n[line 2]",
+        ),
+        (
+            "fun f(a) {}\nf(...\"a\");",
+            "Only lists can be spread, got a.\n[line 2]",
+        ),
+    ];
+    for (source, expected) in cases {
+        test_runtime_error(source.to_string(), expected);
+    }
+
+    let source = "fun f(...a, b) {}".to_string();
+    match Interpreter::from_source(source, vec![])
+        .unwrap()
+        .evaluate_program()
+    {
+        Err(EvaluationError::ParseError(e)) => assert_eq!(
+            format!("{e:?}"),
+            "[line 1] Error at ',': expect ) after rest parameter"
+        ),
+        got => panic!("expected parse error, but got: {got:?}"),
+    }
 }
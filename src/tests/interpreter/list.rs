@@ -0,0 +1,70 @@
+use crate::tests::{test_positive_test, test_runtime_error};
+
+#[test]
+fn literals_and_indexing() {
+    let source = r#"
+var xs = [1, "two", [3, nil], true];
+print xs;
+print xs[1];
+print xs[2][0] + xs[0];
+print [];
+"#
+    .to_string();
+    test_positive_test(source, "[1, \"two\", [3, nil], true]\ntwo\n4\n[]\n");
+}
+
+#[test]
+fn index_assignment_is_shared() {
+    let source = r#"
+var xs = [1, 2, 3];
+var ys = xs;
+fun set(list, i, v) { list[i] = v; }
+ys[0] = 10;
+set(xs, 2, xs[1] = 20);
+print xs;
+print xs == ys;
+print xs == [10, 20, 20];
+"#
+    .to_string();
+    test_positive_test(source, "[10, 20, 20]\ntrue\nfalse\n");
+}
+
+#[test]
+fn invalid_indexes() {
+    test_runtime_error(
+        "var xs = [1, 2];\nprint xs[-1];".to_string(),
+        "Index -1 is negative.\n[line 2]",
+    );
+    test_runtime_error(
+        "var xs = [1, 2];\nxs[2] = 3;".to_string(),
+        "Index 2 is out of range for a list of length 2.\n[line 2]",
+    );
+    test_runtime_error(
+        "print [1][0.5];".to_string(),
+        "Index must be an integer, got 0.5.\n[line 1]",
+    );
+    test_runtime_error(
+        "print [1][\"0\"];".to_string(),
+        "Index must be a number.\n[line 1]",
+    );
+    test_runtime_error(
+        "var s = \"abc\";\nprint s[0];".to_string(),
+        "Only lists and maps can be indexed.\n[line 2]",
+    );
+}
+
+#[test]
+fn self_containing_list_prints() {
+    let source = r#"
+var xs = [1];
+xs[0] = xs;
+print xs;
+var ys = [2];
+var pair = [ys, ys];
+print pair;
+ys[0] = pair;
+print pair;
+"#
+    .to_string();
+    test_positive_test(source, "[[...]]\n[[2], [2]]\n[[[...]], [[...]]]\n");
+}
@@ -0,0 +1,78 @@
+use crate::tests::{test_positive_test, test_runtime_error};
+
+#[test]
+fn literals_and_indexing() {
+    let source = r#"
+var m = {"a": 1, 2: "b", true: [3], nil: nil,};
+print m;
+print m["a"];
+print m[2 * 1];
+print m[true][0];
+print m["missing"];
+print {};
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "{\"a\": 1, 2: \"b\", true: [3], nil: nil}\n1\nb\n3\nnil\n{}\n",
+    );
+}
+
+#[test]
+fn assignment_keeps_insertion_order() {
+    let source = r#"
+var m = {"x": 1, 0: "zero"};
+var alias = m;
+m["y"] = 2;
+alias["x"] = 10;
+m[-0] = "negative zero";
+m[0 / 0] = "nan";
+m[0 / 0] = "same nan";
+print m;
+print m == alias;
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "{\"x\": 10, 0: \"negative zero\", \"y\": 2, NaN: \"same nan\"}\ntrue\n",
+    );
+}
+
+#[test]
+fn unhashable_key() {
+    let source = "var m = {};\nm[[1]] = 2;".to_string();
+    test_runtime_error(
+        source,
+        "Map keys must be numbers, strings, booleans or nil, got [1].\n[line 2]",
+    );
+}
+
+#[test]
+fn string_keys_and_values_print_quoted() {
+    let source = r#"
+var m = {"2": 1, 2: "1"};
+m["say \"hi\""] = ["a\\b", "", "${1}"];
+print m;
+print m["2"];
+print m[2];
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "{\"2\": 1, 2: \"1\", \"say \\\"hi\\\"\": [\"a\\\\b\", \"\", \"1\"]}\n1\n1\n",
+    );
+}
+
+#[test]
+fn self_containing_map_prints() {
+    let source = r#"
+var m = {};
+m["a"] = m;
+print m;
+var n = {"xs": [1]};
+n["xs"][0] = n;
+print n;
+"#
+    .to_string();
+    test_positive_test(source, "{\"a\": {...}}\n{\"xs\": [{...}]}\n");
+}
@@ -0,0 +1,89 @@
+use crate::{
+    interpreter::{EvaluationError, Interpreter},
+    tests::test_positive_test,
+};
+
+#[test]
+fn match_arms() {
+    let source = r#"
+fun describe(x) {
+  match x {
+    0 => return "zero";
+    1 | 2 | 3 => return "small";
+    -5..0 => return "negative";
+    3..10 if x ~/ 1 == x => return "medium";
+    "ten" | true | nil => return "special";
+    _ if x > 100 => return "huge";
+    _ => {
+      var result = "other";
+      return result;
+    }
+  }
+}
+print describe(0);
+print describe(2);
+print describe(-1);
+print describe(-5);
+print describe(7);
+print describe(7.5);
+print describe(10);
+print describe("ten");
+print describe(nil);
+print describe(1000);
+"#
+    .to_string();
+    test_positive_test(
+        source,
+        "zero\nsmall\nnegative\nnegative\nmedium\nother\nother\nspecial\nspecial\nhuge\n",
+    );
+}
+
+#[test]
+fn match_without_matching_arm_and_in_loops() {
+    let source = r#"
+match "a" { "b" => print "b"; }
+for var i = 0; i < 10; i = i + 1 {
+  match i {
+    1 => continue;
+    3 => break;
+    _ => print i;
+  }
+}
+"#
+    .to_string();
+    test_positive_test(source, "0\n2\n");
+}
+
+#[test]
+fn arrow_functions_nested_in_guards() {
+    let source = r#"
+fun apply(f, x) { return f(x); }
+fun describe(x) {
+  match x {
+    _ if apply(n => n < 0, x) => return "negative";
+    _ if [(a, b) => a + b][0](1, 2) == x => return "three";
+    _ if fun () { return (y => y)(true); }() => return "other";
+  }
+}
+print describe(-1);
+print describe(3);
+print describe(4);
+"#
+    .to_string();
+    test_positive_test(source, "negative\nthree\nother\n");
+}
+
+#[test]
+fn match_arm_without_arrow() {
+    let source = "match 1 { 1 print 1; }".to_string();
+    match Interpreter::from_source(source, vec![])
+        .unwrap()
+        .evaluate_program()
+    {
+        Err(EvaluationError::ParseError(e)) => assert_eq!(
+            format!("{e:?}").lines().next(),
+            Some("[line 1] Error at 'print': expect => after match pattern")
+        ),
+        got => panic!("expected parse error, but got: {got:?}"),
+    }
+}
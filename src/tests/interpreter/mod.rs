@@ -15,3 +15,21 @@ mod function;
 
 #[cfg(test)]
 mod closure;
+
+#[cfg(test)]
+mod string;
+
+#[cfg(test)]
+mod class;
+
+#[cfg(test)]
+mod list;
+
+#[cfg(test)]
+mod map;
+
+#[cfg(test)]
+mod match_tests;
+
+#[cfg(test)]
+mod enum_tests;
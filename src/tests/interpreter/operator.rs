@@ -1,6 +1,9 @@
 use core::panic;
 
-use crate::interpreter::{EvaluationError, Interpreter};
+use crate::{
+    interpreter::{EvaluationError, Interpreter},
+    tests::{test_positive_test, test_runtime_error},
+};
 
 #[test]
 fn add() {
@@ -14,40 +17,112 @@ fn add() {
 #[test]
 fn add_bool_nil() {
     let source = include_str!("../../../lox-test/operator/add_bool_nil.lox").to_string();
-    errorneous_test(source, "Operands must be two numbers or two strings.");
+    test_runtime_error(
+        source,
+        "Operands must be two numbers or two strings.\n[line 1]",
+    );
 }
 
 #[test]
 fn add_bool_num() {
     let source = include_str!("../../../lox-test/operator/add_bool_num.lox").to_string();
-    errorneous_test(source, "Operands must be two numbers or two strings.");
+    test_runtime_error(
+        source,
+        "Operands must be two numbers or two strings.\n[line 1]",
+    );
 }
 
 #[test]
 fn add_bool_string() {
     let source = include_str!("../../../lox-test/operator/add_bool_string.lox").to_string();
-    errorneous_test(source, "Operands must be two numbers or two strings.");
+    test_runtime_error(
+        source,
+        "Operands must be two numbers or two strings.\n[line 1]",
+    );
 }
 
 #[test]
 fn add_num_nil() {
     let source = include_str!("../../../lox-test/operator/add_num_nil.lox").to_string();
-    errorneous_test(source, "Operands must be two numbers or two strings.");
+    test_runtime_error(
+        source,
+        "Operands must be two numbers or two strings.\n[line 1]",
+    );
 }
 
 #[test]
 fn add_string_nil() {
     let source = include_str!("../../../lox-test/operator/add_string_nil.lox").to_string();
-    errorneous_test(source, "Operands must be two numbers or two strings.");
+    test_runtime_error(
+        source,
+        "Operands must be two numbers or two strings.\n[line 1]",
+    );
 }
 
-fn errorneous_test(source: String, expected: &str) {
-    let writer = vec![];
-    let mut interpreter = Interpreter::from_source(source, writer).unwrap();
-    match interpreter.evaluate_program() {
-        Err(EvaluationError::Runtime(err)) => {
-            assert_eq!(expected, err.as_str())
-        }
-        got => panic!("expected error, but got: {got:?}"),
+#[test]
+fn modulo_and_integer_division() {
+    let source = r#"
+print 7 % 3;
+print -7 % 3;
+print 7 % -3;
+print 7.5 % 2;
+print 7 ~/ 2;
+print -7 ~/ 2;
+print -7 ~/ 2 * 2 + -7 % 2;
+"#
+    .to_string();
+    test_positive_test(source, "1\n2\n-2\n1.5\n3\n-4\n-7\n");
+}
+
+#[test]
+fn exponent_is_right_associative() {
+    let source = r#"
+print 2 ** 3 ** 2;
+print -2 ** 2;
+print 2 ** -1;
+print 2 * 3 ** 2;
+"#
+    .to_string();
+    test_positive_test(source, "512\n-4\n0.5\n18\n");
+}
+
+#[test]
+fn division_by_zero() {
+    test_runtime_error("print 1 % 0;".to_string(), "Division by zero.\n[line 1]");
+    test_runtime_error("print 1 ~/ 0;".to_string(), "Division by zero.\n[line 1]");
+    test_runtime_error(
+        "print 2 ** nil;".to_string(),
+        "Error: Operands must be numbers.\n[line 1]",
+    );
+}
+
+#[test]
+fn conditional() {
+    let source = r#"
+var a = 1;
+print a == 1 ? "one" : "other";
+print nil or false ? "yes" : "no";
+print a > 5 ? "big" : a > 0 ? "positive" : "negative";
+var b = a < 0 ? -a : a * 10;
+print b;
+true ? print "then" : print "else";
+false ? print "then" : print "else";
+"#
+    .to_string();
+    test_positive_test(source, "one\nno\npositive\n10\nthen\nelse\n");
+}
+
+#[test]
+fn conditional_without_colon() {
+    let source = "print true ? 1 2;".to_string();
+    match Interpreter::from_source(source, vec![])
+        .unwrap()
+        .evaluate_program()
+    {
+        Err(EvaluationError::ParseError(e)) => assert_eq!(
+            format!("{e:?}"),
+            "[line 1] Error at '2': expect : after the then branch of a conditional expression"
+        ),
+        got => panic!("expected parse error, but got: {got:?}"),
     }
 }
@@ -0,0 +1,56 @@
+use crate::{
+    interpreter::{EvaluationError, Interpreter},
+    tests::test_positive_test,
+};
+
+#[test]
+fn interpolation() {
+    let source = r#"
+var name = "Lox";
+var n = 3;
+print "Hello ${name}!";
+print "${n} + 1 = ${n + 1}";
+"#
+    .to_string();
+    test_positive_test(source, "Hello Lox!\n3 + 1 = 4\n");
+}
+
+#[test]
+fn nested_interpolation() {
+    let source = r#"
+fun f() {}
+var name = "Lox";
+print "<${"[${name + "!"}]"}> ${f} ${nil} \${escaped}";
+"#
+    .to_string();
+    test_positive_test(source, "<[Lox!]> <fn f> nil ${escaped}\n");
+}
+
+#[test]
+fn malformed_interpolation() {
+    let cases = [
+        (
+            r#"var a = "x"; print "${a +}" "${a}";"#,
+            "[line 1] Error at '}': expect expression",
+        ),
+        (
+            r#"print "${}" "x";"#,
+            "[line 1] Error at '}': expect expression",
+        ),
+        (
+            r#"print "${"a" + }";"#,
+            "[line 1] Error at '}': expect expression",
+        ),
+    ];
+    for (source, expected) in cases {
+        match Interpreter::from_source(source.to_string(), vec![])
+            .unwrap()
+            .evaluate_program()
+        {
+            Err(EvaluationError::ParseError(e)) => {
+                assert_eq!(format!("{e:?}").lines().next(), Some(expected))
+            }
+            got => panic!("expected parse error, but got: {got:?}"),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::interpreter::Interpreter;
+use crate::{interpreter::Interpreter, parser::Parser, tests::test_positive_test};
 
 #[test]
 fn while_syntax() {
@@ -8,3 +8,39 @@ fn while_syntax() {
     interpreter.evaluate_program().unwrap();
     assert_eq!(interpreter.writer(), b"1\n2\n3\n0\n1\n2\n");
 }
+
+#[test]
+fn break_and_continue() {
+    let source = r#"
+var i = 0;
+while true {
+  i = i + 1;
+  if (i == 2) continue;
+  if (i > 4) break;
+  print i;
+}
+for var j = 0; j < 5; j = j + 1 {
+  if (j == 1) { continue; }
+  for var k = 0; k < 5; k = k + 1 {
+    if (k == 1) break;
+    print j * 10 + k;
+  }
+  if (j == 3) break;
+}
+"#;
+    test_positive_test(source.to_string(), "1\n3\n4\n0\n20\n30\n");
+}
+
+#[test]
+fn break_and_continue_outside_of_loop() {
+    let source = "break;\nwhile true { fun f() { continue; } break; }\n";
+    let mut parser = Parser::from_source(source.to_string()).unwrap();
+    let (_, errors) = parser.parse_program_with_errors();
+    assert_eq!(
+        errors.iter().map(|e| format!("{e:?}")).collect::<Vec<_>>(),
+        vec![
+            "[line 1] Error at 'break': Can't use 'break' outside of a loop.",
+            "[line 2] Error at 'continue': Can't use 'continue' outside of a loop.",
+        ]
+    );
+}
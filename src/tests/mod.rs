@@ -1,12 +1,13 @@
 use core::panic;
+use std::io::Read;
 
 use crate::{
-    interpreter::Interpreter,
+    interpreter::{EvaluationError, Interpreter},
     parser::{
-        expression::{Expression, Statement},
-        Parser,
+        expression::{ExpressionKind, StatementKind},
+        ParseError, Parser,
     },
-    token::Scanner,
+    token::{Scanner, Span, Token},
 };
 
 #[cfg(test)]
@@ -33,6 +34,14 @@ pub(crate) fn test_positive_test(source: String, expected: &str) {
     interpreter.evaluate_program().unwrap();
     assert_eq!(std::str::from_utf8(interpreter.writer()).unwrap(), expected);
 }
+/// Runs `source`, which must fail with the runtime error `expected`.
+pub(crate) fn test_runtime_error(source: String, expected: &str) {
+    let mut interpreter = Interpreter::from_source(source.clone(), vec![]).unwrap();
+    match interpreter.evaluate_program() {
+        Err(EvaluationError::Runtime(e)) => assert_eq!(e, expected, "{source}"),
+        got => panic!("expected runtime error for {source}, but got: {got:?}"),
+    }
+}
 #[test]
 fn it_works() {
     let src = "5;".to_string();
@@ -42,13 +51,13 @@ fn it_works() {
     assert_eq!(1, statements.len());
 
     let stmt = &statements[0];
-    let expr = match stmt {
-        Statement::Expression(expr) => expr,
+    let expr = match &stmt.kind {
+        StatementKind::Expression(expr) => expr,
         stmt => panic!("expected ExpressionStatement, found : {stmt:?}"),
     };
 
-    let _ = match expr {
-        Expression::NumberLiteral(val) => val,
+    let _ = match &expr.kind {
+        ExpressionKind::NumberLiteral(val) => val,
         expr => panic!("expected NumberLiteral, found: {expr:?}"),
     };
 }
@@ -83,6 +92,460 @@ fn scanning_keywords_positive_tests() {
     scanning_test_from_source(source);
 }
 
+#[test]
+fn scanning_token_spans() {
+    let source = "var x =\n  \"a\nb\" + 12; // done\n".to_string();
+    let scanner = Scanner::new(source);
+    let spans = scanner
+        .iter()
+        .map(|token| {
+            let span = token.unwrap().span;
+            (span.start, span.end, span.line, span.column)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            (0, 3, 1, 1),
+            (4, 5, 1, 5),
+            (6, 7, 1, 7),
+            (10, 15, 2, 3),
+            (16, 17, 3, 4),
+            (18, 20, 3, 6),
+            (20, 21, 3, 8),
+            (30, 30, 4, 1),
+        ]
+    );
+}
+
+#[test]
+fn scanning_block_comments() {
+    let source = "/* license\n /* nested */ still comment */ a /* b */ c\n/* open".to_string();
+    let scanner = Scanner::new(source);
+    let mut tokens = scanner.iter();
+
+    let a = tokens.next().unwrap().unwrap();
+    assert_eq!(format!("{:?}", a.token), "IDENTIFIER a null");
+    assert_eq!(a.span.line, 2);
+    let c = tokens.next().unwrap().unwrap();
+    assert_eq!(format!("{:?}", c.token), "IDENTIFIER c null");
+    match tokens.next().unwrap() {
+        Err(e) => assert_eq!(
+            format!("{e:?}"),
+            "[line 3] Error: Unterminated block comment."
+        ),
+        Ok(token) => panic!("expected unterminated block comment, got {:?}", token.token),
+    }
+    assert_eq!(
+        format!("{:?}", tokens.next().unwrap().unwrap().token),
+        "EOF  null"
+    );
+}
+
+#[test]
+fn scanning_string_escapes() {
+    let source = r#""tab\there" "\u{1F600}\"" "bad \q and \u{110000}""#.to_string();
+    let scanner = Scanner::new(source);
+    let mut tokens = scanner.iter();
+
+    let token = tokens.next().unwrap().unwrap().token;
+    assert_eq!(format!("{token:?}"), "STRING \"tab\\there\" tab\there");
+    let token = tokens.next().unwrap().unwrap().token;
+    assert_eq!(
+        format!("{token:?}"),
+        "STRING \"\\u{1F600}\\\"\" \u{1F600}\""
+    );
+    match tokens.next().unwrap() {
+        Err(e) => assert_eq!(
+            format!("{e:?}"),
+            "[line 1] Error: Invalid escape sequence: \\q"
+        ),
+        Ok(token) => panic!("expected invalid escape sequence, got {:?}", token.token),
+    }
+    assert_eq!(
+        format!("{:?}", tokens.next().unwrap().unwrap().token),
+        "EOF  null"
+    );
+}
+
+#[test]
+fn string_escapes_are_resolved_when_printed() {
+    let source = r#"print "say \"hi\"\n\\ \u{e9}";"#.to_string();
+    test_positive_test(source, "say \"hi\"\n\\ \u{e9}\n");
+}
+
+#[test]
+fn scanning_extended_number_literals() {
+    let source =
+        "0xFF 0b1010 1e-9 2.5E3 1_000_000 1e 0x 1__0 0b102 1_ 0bar 0xFG 0x1__F".to_string();
+    let scanner = Scanner::new(source);
+    let tokens = scanner
+        .iter()
+        .map(|token| match token {
+            Ok(token) => format!("{:?}", token.token),
+            Err(e) => format!("{e:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            "NUMBER 0xFF 255.0",
+            "NUMBER 0b1010 10.0",
+            "NUMBER 1e-9 1e-9",
+            "NUMBER 2.5E3 2500.0",
+            "NUMBER 1_000_000 1000000.0",
+            "NUMBER 1 1.0",
+            "IDENTIFIER e null",
+            "NUMBER 0 0.0",
+            "IDENTIFIER x null",
+            "[line 1] Error: Malformed number: 1__0",
+            "NUMBER 0b10 2.0",
+            "NUMBER 2 2.0",
+            "[line 1] Error: Malformed number: 1_",
+            "NUMBER 0 0.0",
+            "IDENTIFIER bar null",
+            "NUMBER 0xF 15.0",
+            "IDENTIFIER G null",
+            "[line 1] Error: Malformed number: 0x1__F",
+            "EOF  null",
+        ]
+    );
+}
+
+#[test]
+fn scanning_interpolated_strings() {
+    let source = r#""a${1}b${ {} }c" "${}""#.to_string();
+    let scanner = Scanner::new(source);
+    let tokens = scanner
+        .iter()
+        .map(|token| format!("{:?}", token.unwrap().token))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            "STRING_INTERPOLATION \"a\" a",
+            "NUMBER 1 1.0",
+            "STRING_INTERPOLATION_MIDDLE \"b\" b",
+            "LEFT_BRACE { null",
+            "RIGHT_BRACE } null",
+            "STRING_INTERPOLATION_END \"c\" c",
+            "STRING_INTERPOLATION \"\" ",
+            "STRING_INTERPOLATION_END \"\" ",
+            "EOF  null",
+        ]
+    );
+}
+
+#[test]
+fn scanning_unicode_and_crlf() {
+    let source =
+        "\u{FEFF}#!/usr/bin/env lox\r\nvar café = \"naïve\";\r\nπ2 cafe\u{301} § 1;".to_string();
+    let scanner = Scanner::new(source);
+    let tokens = scanner
+        .iter()
+        .map(|token| match token {
+            Ok(token) => format!(
+                "{}:{} {:?}",
+                token.span.line, token.span.column, token.token
+            ),
+            Err(e) => format!("{e:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            "2:1 VAR var null",
+            "2:5 IDENTIFIER café null",
+            "2:10 EQUAL = null",
+            "2:12 STRING \"naïve\" naïve",
+            "2:19 SEMICOLON ; null",
+            "3:1 IDENTIFIER π2 null",
+            "3:4 IDENTIFIER cafe\u{301} null",
+            "[line 3] Error: Unexpected character: §",
+            "3:12 NUMBER 1 1.0",
+            "3:13 SEMICOLON ; null",
+            "3:14 EOF  null",
+        ]
+    );
+}
+
+#[test]
+fn scanning_identifiers_follows_xid() {
+    // NOTE: each of these is alphabetic or numeric, but only some are `XID_Start` or
+    // `XID_Continue`: U+0345 and U+00B7 and U+203F only continue an identifier, U+2E2F and
+    // U+00B2 neither start nor continue one.
+    let source = "a\u{B7}b x\u{203F}y \u{345} k\u{345} \u{2E2F} x\u{B2}".to_string();
+    let tokens = Scanner::new(source)
+        .iter()
+        .map(|token| match token {
+            Ok(token) => format!("{:?}", token.token),
+            Err(e) => format!("{e:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            "IDENTIFIER a\u{B7}b null",
+            "IDENTIFIER x\u{203F}y null",
+            "[line 1] Error: Unexpected character: \u{345}",
+            "IDENTIFIER k\u{345} null",
+            "[line 1] Error: Unexpected character: \u{2E2F}",
+            "IDENTIFIER x null",
+            "[line 1] Error: Unexpected character: \u{B2}",
+            "EOF  null",
+        ]
+    );
+}
+
+#[test]
+fn scanning_with_trivia_is_lossless() {
+    let source = "\u{FEFF}#!/usr/bin/env lox\r\n/* header /* nested */ */\r\nvar x = \"a ${ {1} } \\\"b\\\"\";\t// trailing\n  print 0x1_F;\n"
+        .to_string();
+    let scanner = Scanner::with_trivia(source.clone());
+    let mut lossless = vec![];
+    let mut trivia = vec![];
+    for token in scanner.iter() {
+        let token = token.unwrap();
+        assert_eq!(token.lexeme.len(), token.span.end - token.span.start);
+        lossless.extend_from_slice(&token.lexeme);
+        match token.token {
+            Token::COMMENT(_) | Token::WHITESPACE(_) => trivia.push(format!("{:?}", token.token)),
+            _ => (),
+        }
+    }
+    assert_eq!(std::str::from_utf8(&lossless).unwrap(), source);
+    assert_eq!(
+        trivia[..4],
+        [
+            "WHITESPACE \"\\u{feff}\" null",
+            "COMMENT \"#!/usr/bin/env lox\\r\" null",
+            "WHITESPACE \"\\n\" null",
+            "COMMENT \"/* header /* nested */ */\" null",
+        ]
+    );
+}
+
+#[test]
+fn all_lexical_errors_are_reported() {
+    let source = "print 1 @ 2;\nvar = 3 #;\nprint \"unterminated;".to_string();
+    let mut parser = Parser::from_source(source).unwrap();
+    match parser.parse_program() {
+        Err(ParseError::LexicalErrors(errors)) => assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.span.line, e.span.column))
+                .collect::<Vec<_>>(),
+            vec![(1, 9), (2, 9), (3, 7)]
+        ),
+        Err(e) => panic!("expected lexical errors, got {e:?}"),
+        Ok(_) => panic!("expected lexical errors"),
+    };
+
+    match Parser::from_source("@ $".to_string()) {
+        Err(e) => assert_eq!(
+            format!("{e:?}"),
+            "[line 1] Error: Unexpected character: @\n[line 1] Error: Unexpected character: $"
+        ),
+        Ok(_) => panic!("expected lexical errors"),
+    };
+}
+
+#[test]
+fn tokens_and_errors_as_json() {
+    let scanner = Scanner::new("print \"tab\\t\\u{1F600}\" 0x10; #".to_string());
+    let lines = scanner
+        .iter()
+        .map(|token| match token {
+            Ok(token) => token.to_json(),
+            Err(e) => e.to_json(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            r#"{"kind":"PRINT","lexeme":"print","literal":null,"position":{"start":0,"end":5,"line":1,"column":1}}"#,
+            r#"{"kind":"STRING","lexeme":"\"tab\\t\\u{1F600}\"","literal":"tab\t😀","position":{"start":6,"end":22,"line":1,"column":7}}"#,
+            r#"{"kind":"NUMBER","lexeme":"0x10","literal":16.0,"position":{"start":23,"end":27,"line":1,"column":24}}"#,
+            r#"{"kind":"SEMICOLON","lexeme":";","literal":null,"position":{"start":27,"end":28,"line":1,"column":28}}"#,
+            r#"{"error":"Unexpected character: #","position":{"start":29,"end":30,"line":1,"column":30}}"#,
+            r#"{"kind":"EOF","lexeme":"","literal":null,"position":{"start":30,"end":30,"line":1,"column":31}}"#,
+        ]
+    );
+}
+
+#[test]
+fn identifiers_are_interned() {
+    let scanner = Scanner::new("foo bar foo café".to_string());
+    let symbols = scanner
+        .iter()
+        .filter_map(|token| token.unwrap().token.get_symbol())
+        .collect::<Vec<_>>();
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols[0], symbols[2]);
+    assert_ne!(symbols[0], symbols[1]);
+
+    let interner = scanner.interner();
+    let interner = interner.borrow();
+    let names = symbols
+        .iter()
+        .map(|symbol| std::str::from_utf8(interner.resolve(*symbol)).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["foo", "bar", "foo", "café"]);
+}
+
+/// A reader that hands out its source one to three bytes at a time, splitting tokens and UTF-8
+/// sequences across reads.
+struct TrickleReader {
+    source: Vec<u8>,
+    position: usize,
+}
+
+impl Read for TrickleReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = (self.position % 3 + 1)
+            .min(buf.len())
+            .min(self.source.len() - self.position);
+        buf[..len].copy_from_slice(&self.source[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+#[test]
+fn streamed_scanning_matches_in_memory_scanning() {
+    let describe = |scanner: Scanner| {
+        scanner
+            .iter()
+            .map(|token| match token {
+                Ok(token) => format!("{:?} {:?} {:?}", token.token, token.span, token.lexeme),
+                Err(e) => format!("{e:?} {:?}", e.span),
+            })
+            .collect::<Vec<_>>()
+    };
+    let long_string = "x".repeat(20_000);
+    let source = format!(
+        "/* café /* nested */ */\r\nvar café = \"a ${{ {{1}} }} \\u{{1F600}}\";\n\
+         print 0x1_F + 1e-3 + 1__0; @ \"{long_string}\" // done\n\"unterminated"
+    );
+    let source_reader = TrickleReader {
+        source: source.clone().into_bytes(),
+        position: 0,
+    };
+    assert_eq!(
+        describe(Scanner::from_reader(source_reader)),
+        describe(Scanner::new(source))
+    );
+}
+
+#[test]
+fn streamed_scanning_reports_read_errors() {
+    struct FailingReader;
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk on fire"))
+        }
+    }
+
+    let errors = Scanner::from_reader(FailingReader)
+        .iter()
+        .filter_map(Result::err)
+        .map(|e| format!("{e:?}"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec!["[line 1] Error: Failed to read source: disk on fire"]
+    );
+}
+
+#[test]
+fn parser_recovers_and_reports_every_error() {
+    let source = "var = 1;\nprint 2\nvar y = 3;\nfun f() { print ; var z = 1; }\nprint \"ok\";\n";
+    let mut parser = Parser::from_source(source.to_string()).unwrap();
+    let (statements, errors) = parser.parse_program_with_errors();
+    assert_eq!(
+        errors.iter().map(|e| format!("{e:?}")).collect::<Vec<_>>(),
+        vec![
+            "[line 1] Error at '=': expect Identifier",
+            "[line 3] Error at 'var': expect ;",
+            "[line 4] Error at ';': expect expression",
+        ]
+    );
+    assert_eq!(
+        statements
+            .iter()
+            .map(|stmt| format!("{stmt:?}"))
+            .collect::<Vec<_>>(),
+        vec!["var y = 3.0;", "<fn f>", "print ok;"]
+    );
+
+    let mut parser = Parser::from_source("print ;\nprint 1 +;".to_string()).unwrap();
+    match parser.parse_program() {
+        Err(ParseError::Multiple(errors)) => assert_eq!(errors.len(), 2),
+        Err(e) => panic!("expected multiple errors, got {e:?}"),
+        Ok(_) => panic!("expected parse errors"),
+    }
+}
+
+#[test]
+fn ast_nodes_have_spans() {
+    let source = "var x = 1;\nif (x) {\n  print  x + -2 * x;\n}\n";
+    let mut parser = Parser::from_source(source.to_string()).unwrap();
+    let statements = parser.parse_program().unwrap();
+    let text = |span: Span| &source[span.start..span.end];
+
+    assert_eq!(text(statements[0].span), "var x = 1");
+    assert_eq!(
+        text(statements[1].span),
+        "if (x) {\n  print  x + -2 * x;\n}"
+    );
+    let StatementKind::IfStatement(if_statement) = &statements[1].kind else {
+        panic!("expected an if statement, got {:?}", statements[1]);
+    };
+    let StatementKind::Block(block) = &if_statement.if_block.kind else {
+        panic!("expected a block, got {:?}", if_statement.if_block);
+    };
+    let StatementKind::Print(expr) = &block[0].kind else {
+        panic!("expected a print statement, got {:?}", block[0]);
+    };
+    assert_eq!((block[0].span.line, block[0].span.column), (3, 3));
+    assert_eq!(text(expr.span), "x + -2 * x");
+    let ExpressionKind::InfixExpression { right_expr, .. } = &expr.kind else {
+        panic!("expected an infix expression, got {expr:?}");
+    };
+    assert_eq!(text(right_expr.span), "-2 * x");
+    assert_eq!((right_expr.span.line, right_expr.span.column), (3, 14));
+}
+
+#[test]
+fn runtime_errors_report_the_line_of_the_expression() {
+    let source = "var x = \"a\";\nprint -x;\nprint 1;\n".to_string();
+    test_runtime_error(source, "Error: Operand must be a number.\n[line 2]");
+
+    let source = "print 1;\n{\n  print y;\n}".to_string();
+    let mut interpreter = Interpreter::from_source(source, vec![]).unwrap();
+    match interpreter.evaluate_program() {
+        Err(e) => assert_eq!(format!("{e:?}"), "undefined variable 'y'\n[line 3]"),
+        Ok(_) => panic!("expected a runtime error"),
+    }
+    let cases = [
+        (
+            "print 1;\nprint 1 < \"a\";",
+            "Error: Operands must be numbers.\n[line 2]",
+        ),
+        (
+            "print 1;\n\n\"a\"();",
+            "Callee must be a function.\n[line 3]",
+        ),
+        (
+            "class A {}\nA(1);",
+            "Expected 0 arguments but got 1.\n[line 2]",
+        ),
+    ];
+    for (source, expected) in cases {
+        test_runtime_error(source.to_string(), expected);
+    }
+}
+
 #[test]
 fn basic_print_test() {
     let source = "print \"kumarmo2\";".to_string();
@@ -105,8 +568,31 @@ fn scanning_test_from_source(source: &str) {
 
     let expect_lines = expect_lines.into_iter();
     for expect in expect_lines {
-        let token = tokens.next().unwrap().unwrap();
+        let token = tokens.next().unwrap().unwrap().token;
         let dbg_print = format!("{token:?}");
         assert_eq!(dbg_print, expect);
     }
 }
+
+#[test]
+fn malformed_programs_do_not_crash_the_parser() {
+    let lexemes = [
+        "(", ")", "{", "}", "[", "]", "*", ".", ",", ":", "?", "=>", "...", "..", "|", "+", "-",
+        "/", "**", "%", "~/", "+=", "-=", "*=", "/=", "++", "--", ";", "=", "==", "!", "!=", "<",
+        "<=", ">", ">=", "x", "1", "\"s\"", "and", "class", "else", "enum", "false", "for", "fun",
+        "if", "nil", "or", "print", "return", "super", "this", "true", "var", "while", "break",
+        "continue", "match", "_",
+    ];
+    for first in lexemes {
+        for second in lexemes {
+            for source in [
+                format!("{first} {second}"),
+                format!("x {first} {second} y;"),
+                format!("match x {{ {first} {second} => 1; }}"),
+            ] {
+                let mut parser = Parser::from_source(source).unwrap();
+                parser.parse_program_with_errors();
+            }
+        }
+    }
+}
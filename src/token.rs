@@ -1,41 +1,89 @@
 #![allow(dead_code)]
 
 use core::str;
-use std::{collections::HashMap, str::FromStr};
+use std::{cell::RefCell, collections::HashMap, io::Read, rc::Rc, str::FromStr};
 
-use bytes::Bytes;
+use bytes::{BufMut, Bytes, BytesMut};
 use lazy_static::lazy_static;
 
-use crate::parser::expression::Precedence;
+use crate::{
+    parser::expression::Precedence,
+    symbol::{Interner, Symbol},
+    xid::{is_xid_continue, is_xid_start},
+};
 
-pub enum LexicalError {
-    UnExpectedToken { ch: char, line: u32 }, // Error token.
-    UnterminatedString { line: u32 },        // Error Token.
+pub(crate) enum LexicalErrorKind {
+    UnExpectedToken { ch: char }, // Error token.
+    UnterminatedString,           // Error Token.
+    UnterminatedBlockComment,
+    InvalidEscapeSequence { sequence: String },
+    MalformedNumber { lexeme: String },
+    InvalidUtf8,
+    Io(String),
 }
 
-impl std::fmt::Debug for LexicalError {
+impl std::fmt::Display for LexicalErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            LexicalError::UnExpectedToken { ch, line } => f.write_fmt(format_args!(
-                "[line {line}] Error: Unexpected character: {ch}"
-            )),
-            LexicalError::UnterminatedString { line } => {
-                f.write_fmt(format_args!("[line {line}] Error: Unterminated string."))
+            LexicalErrorKind::UnExpectedToken { ch } => {
+                f.write_fmt(format_args!("Unexpected character: {ch}"))
+            }
+            LexicalErrorKind::UnterminatedString => f.write_str("Unterminated string."),
+            LexicalErrorKind::UnterminatedBlockComment => {
+                f.write_str("Unterminated block comment.")
+            }
+            LexicalErrorKind::InvalidEscapeSequence { sequence } => {
+                f.write_fmt(format_args!("Invalid escape sequence: {sequence}"))
             }
+            LexicalErrorKind::MalformedNumber { lexeme } => {
+                f.write_fmt(format_args!("Malformed number: {lexeme}"))
+            }
+            LexicalErrorKind::InvalidUtf8 => f.write_str("Invalid UTF-8 sequence."),
+            LexicalErrorKind::Io(e) => f.write_fmt(format_args!("Failed to read source: {e}")),
         }
     }
 }
 
+/// A lexical error along with the span of the source that couldn't be scanned.
+pub struct LexicalError {
+    pub(crate) kind: LexicalErrorKind,
+    pub(crate) span: Span,
+}
+
+impl LexicalError {
+    /// The error as a single line JSON object, for `tokenize --format json`.
+    pub(crate) fn to_json(&self) -> String {
+        format!(
+            "{{\"error\":{},\"position\":{}}}",
+            json_string(&self.kind.to_string()),
+            self.span.to_json()
+        )
+    }
+}
+
+impl std::fmt::Debug for LexicalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "[line {}] Error: {}",
+            self.span.line, self.kind
+        ))
+    }
+}
+
 lazy_static! {
     pub(crate) static ref KEYWORDS: HashMap<&'static str, Token> = {
         let mut m = HashMap::new();
         m.insert("and", Token::And);
+        m.insert("break", Token::Break);
         m.insert("class", Token::Class);
+        m.insert("continue", Token::Continue);
         m.insert("else", Token::Else);
+        m.insert("enum", Token::Enum);
         m.insert("false", Token::False);
         m.insert("for", Token::For);
         m.insert("fun", Token::Fun);
         m.insert("if", Token::If);
+        m.insert("match", Token::Match);
         m.insert("nil", Token::Nil);
         m.insert("or", Token::Or);
         m.insert("print", Token::Print);
@@ -49,38 +97,65 @@ lazy_static! {
     };
 }
 
+// NOTE: most variants are named after the kinds `tokenize` prints, e.g. `STAR` and `SEMICOLON`.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub(crate) enum Token {
-    LParen, // `(`
-    RParen, // `)`
-    LBrace, // `{`
-    RBrace, // `}`
-    STAR,   //  `*`
-    DOT,    // `.`
-    COMMA,  // `,`
-    PLUS,   // `+`
-    MINUS,  // `-`
-    SLASH,  // `/`
+    LParen,     // `(`
+    RParen,     // `)`
+    LBrace,     // `{`
+    RBrace,     // `}`
+    LBracket,   // `[`
+    RBracket,   // `]`
+    STAR,       //  `*`
+    DOT,        // `.`
+    COMMA,      // `,`
+    COLON,      // `:`
+    QUESTION,   // `?`
+    ARROW,      // `=>`
+    DOTDOTDOT,  // `...`, rest parameters and spread arguments.
+    DOTDOT,     // `..`, range patterns.
+    PIPE,       // `|`, between the alternatives of a pattern.
+    PLUS,       // `+`
+    MINUS,      // `-`
+    SLASH,      // `/`
+    STARSTAR,   // `**`
+    PERCENT,    // `%`
+    TILDESLASH, // `~/`, integer division.
+    PLUSEQUAL,  // `+=`
+    MINUSEQUAL, // `-=`
+    STAREQUAL,  // `*=`
+    SLASHEQUAL, // `/=`
+    PLUSPLUS,   // `++`
+    MINUSMINUS, // `--`
     COMMENT(Bytes),
-    SEMICOLON,    // `;`
-    EQUAL,        // =
-    EQUALEQUAL,   // ==
-    BANG,         // !
-    BANGEQUAL,    // !=
-    LESS,         // <
-    LESSEQUAL,    // <=
-    GREATER,      // >
-    GREATEREQUAL, // >=
-    StringLiteral(Bytes),
+    WHITESPACE(Bytes),
+    SEMICOLON,                               // `;`
+    EQUAL,                                   // =
+    EQUALEQUAL,                              // ==
+    BANG,                                    // !
+    BANGEQUAL,                               // !=
+    LESS,                                    // <
+    LESSEQUAL,                               // <=
+    GREATER,                                 // >
+    GREATEREQUAL,                            // >=
+    StringLiteral(Bytes, Bytes), // value with escapes resolved, raw lexeme between the quotes.
+    StringInterpolation(Bytes, Bytes), // part of a string literal that precedes a `${`.
+    StringInterpolationMiddle(Bytes, Bytes), // part between a `}` and the next `${`.
+    StringInterpolationEnd(Bytes, Bytes), // part between the last `}` and the closing quote.
     NumberLiteral(f64, Bytes),
-    Identifier(Bytes),
+    Identifier(Symbol, Bytes), // interned symbol, name.
     And,
+    Break,
     Class,
+    Continue,
     Else,
+    Enum,
     False,
     For,
     Fun,
     If,
+    Match,
     Nil,
     Or,
     Print,
@@ -96,25 +171,112 @@ pub(crate) enum Token {
 impl Token {
     pub(crate) fn get_bytes(&self) -> Option<Bytes> {
         match self {
-            Token::StringLiteral(b) | Token::Identifier(b) | Token::COMMENT(b) => Some(b.clone()),
+            Token::StringLiteral(b, _) | Token::Identifier(_, b) | Token::COMMENT(b) => {
+                Some(b.clone())
+            }
+            _ => None,
+        }
+    }
+    pub(crate) fn get_symbol(&self) -> Option<Symbol> {
+        match self {
+            Token::Identifier(symbol, _) => Some(*symbol),
             _ => None,
         }
     }
     pub(crate) fn get_precedence(&self) -> Precedence {
         match self {
             Token::PLUS | Token::MINUS => Precedence::Sum,
-            Token::SLASH | Token::STAR => Precedence::Product,
+            Token::SLASH | Token::STAR | Token::PERCENT | Token::TILDESLASH => Precedence::Product,
+            Token::STARSTAR => Precedence::Exponent,
             Token::And => Precedence::And,
             Token::Or => Precedence::Or,
+            Token::QUESTION => Precedence::Conditional,
             Token::LESS | Token::GREATER | Token::LESSEQUAL | Token::GREATEREQUAL => {
                 Precedence::LessGreater
             }
-            Token::EQUAL => Precedence::Assign,
+            Token::EQUAL
+            | Token::PLUSEQUAL
+            | Token::MINUSEQUAL
+            | Token::STAREQUAL
+            | Token::SLASHEQUAL => Precedence::Assign,
+            // NOTE: as postfix operators.
+            Token::PLUSPLUS | Token::MINUSMINUS => Precedence::Call,
             Token::BANGEQUAL | Token::EQUALEQUAL => Precedence::Equals,
-            Token::LParen => Precedence::Call,
+            Token::LParen | Token::DOT | Token::LBracket => Precedence::Call,
             _ => Precedence::Lowest,
         }
     }
+
+    /// The kind of the token, as printed by `tokenize`.
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match self {
+            Token::LParen => "LEFT_PAREN",
+            Token::RParen => "RIGHT_PAREN",
+            Token::LBrace => "LEFT_BRACE",
+            Token::RBrace => "RIGHT_BRACE",
+            Token::LBracket => "LEFT_BRACKET",
+            Token::RBracket => "RIGHT_BRACKET",
+            Token::STAR => "STAR",
+            Token::DOT => "DOT",
+            Token::COMMA => "COMMA",
+            Token::COLON => "COLON",
+            Token::QUESTION => "QUESTION",
+            Token::ARROW => "ARROW",
+            Token::DOTDOTDOT => "DOT_DOT_DOT",
+            Token::DOTDOT => "DOT_DOT",
+            Token::PIPE => "PIPE",
+            Token::PLUS => "PLUS",
+            Token::MINUS => "MINUS",
+            Token::SLASH => "SLASH",
+            Token::STARSTAR => "STAR_STAR",
+            Token::PERCENT => "PERCENT",
+            Token::TILDESLASH => "TILDE_SLASH",
+            Token::PLUSEQUAL => "PLUS_EQUAL",
+            Token::MINUSEQUAL => "MINUS_EQUAL",
+            Token::STAREQUAL => "STAR_EQUAL",
+            Token::SLASHEQUAL => "SLASH_EQUAL",
+            Token::PLUSPLUS => "PLUS_PLUS",
+            Token::MINUSMINUS => "MINUS_MINUS",
+            Token::COMMENT(_) => "COMMENT",
+            Token::WHITESPACE(_) => "WHITESPACE",
+            Token::SEMICOLON => "SEMICOLON",
+            Token::EQUAL => "EQUAL",
+            Token::EQUALEQUAL => "EQUAL_EQUAL",
+            Token::BANG => "BANG",
+            Token::BANGEQUAL => "BANG_EQUAL",
+            Token::LESS => "LESS",
+            Token::LESSEQUAL => "LESS_EQUAL",
+            Token::GREATER => "GREATER",
+            Token::GREATEREQUAL => "GREATER_EQUAL",
+            Token::StringLiteral(..) => "STRING",
+            Token::StringInterpolation(..) => "STRING_INTERPOLATION",
+            Token::StringInterpolationMiddle(..) => "STRING_INTERPOLATION_MIDDLE",
+            Token::StringInterpolationEnd(..) => "STRING_INTERPOLATION_END",
+            Token::NumberLiteral(..) => "NUMBER",
+            Token::Identifier(..) => "IDENTIFIER",
+            Token::And => "AND",
+            Token::Break => "BREAK",
+            Token::Class => "CLASS",
+            Token::Continue => "CONTINUE",
+            Token::Else => "ELSE",
+            Token::Enum => "ENUM",
+            Token::False => "FALSE",
+            Token::For => "FOR",
+            Token::Fun => "FUN",
+            Token::If => "IF",
+            Token::Match => "MATCH",
+            Token::Nil => "NIL",
+            Token::Or => "OR",
+            Token::Print => "PRINT",
+            Token::Return => "RETURN",
+            Token::Super => "SUPER",
+            Token::This => "THIS",
+            Token::True => "TRUE",
+            Token::Var => "VAR",
+            Token::While => "WHILE",
+            Token::EOF => "EOF",
+        }
+    }
 }
 
 impl std::fmt::Display for Token {
@@ -124,9 +286,17 @@ impl std::fmt::Display for Token {
             Token::RParen => f.write_str(")"),
             Token::LBrace => f.write_str("{"),
             Token::RBrace => f.write_str("}"),
+            Token::LBracket => f.write_str("["),
+            Token::RBracket => f.write_str("]"),
             Token::STAR => f.write_str("*"),
             Token::DOT => f.write_str("."),
             Token::COMMA => f.write_str(","),
+            Token::COLON => f.write_str(":"),
+            Token::QUESTION => f.write_str("?"),
+            Token::ARROW => f.write_str("=>"),
+            Token::DOTDOTDOT => f.write_str("..."),
+            Token::DOTDOT => f.write_str(".."),
+            Token::PIPE => f.write_str("|"),
             Token::PLUS => f.write_str("+"),
             Token::MINUS => f.write_str("-"),
             Token::SEMICOLON => f.write_str(";"),
@@ -139,26 +309,41 @@ impl std::fmt::Display for Token {
             Token::GREATER => f.write_str(">"),
             Token::GREATEREQUAL => f.write_str(">="),
             Token::SLASH => f.write_str("/"),
-            Token::COMMENT(_) => unimplemented!("Will not display comment"),
-            Token::StringLiteral(s) => {
-                // TODO: remove unsafe
-                let string =
-                    unsafe { String::from_str(std::str::from_utf8_unchecked(&s)).unwrap() };
-                f.write_fmt(format_args!("{string}"))
+            Token::STARSTAR => f.write_str("**"),
+            Token::PERCENT => f.write_str("%"),
+            Token::TILDESLASH => f.write_str("~/"),
+            Token::PLUSEQUAL => f.write_str("+="),
+            Token::MINUSEQUAL => f.write_str("-="),
+            Token::STAREQUAL => f.write_str("*="),
+            Token::SLASHEQUAL => f.write_str("/="),
+            Token::PLUSPLUS => f.write_str("++"),
+            Token::MINUSMINUS => f.write_str("--"),
+            Token::COMMENT(b) | Token::WHITESPACE(b) => f.write_str(&String::from_utf8_lossy(b)),
+            Token::StringLiteral(s, _) => f.write_str(&String::from_utf8_lossy(s)),
+            Token::StringInterpolation(s, _) => {
+                f.write_fmt(format_args!("{}${{", String::from_utf8_lossy(s)))
+            }
+            Token::StringInterpolationMiddle(s, _) => {
+                f.write_fmt(format_args!("}}{}${{", String::from_utf8_lossy(s)))
+            }
+            Token::StringInterpolationEnd(s, _) => {
+                f.write_fmt(format_args!("}}{}", String::from_utf8_lossy(s)))
             }
             Token::NumberLiteral(number, _) => f.write_fmt(format_args!("{}", number)),
-            Token::Identifier(identifier_bytes) => f.write_fmt(format_args!(
-                // TODO: remove unwraps.
-                "{}",
-                String::from_str(std::str::from_utf8(identifier_bytes.as_ref()).unwrap()).unwrap()
-            )),
+            Token::Identifier(_, identifier_bytes) => {
+                f.write_str(&String::from_utf8_lossy(identifier_bytes))
+            }
             Token::And => f.write_str("and"),
+            Token::Break => f.write_str("break"),
             Token::Class => f.write_str("class"),
+            Token::Continue => f.write_str("continue"),
             Token::Else => f.write_str("else"),
+            Token::Enum => f.write_str("enum"),
             Token::False => f.write_str("false"),
             Token::For => f.write_str("for"),
             Token::Fun => f.write_str("fun"),
             Token::If => f.write_str("if"),
+            Token::Match => f.write_str("match"),
             Token::Nil => f.write_str("nil"),
             Token::Or => f.write_str("or"),
             Token::Print => f.write_str("print"),
@@ -180,9 +365,17 @@ impl std::fmt::Debug for Token {
             Token::RParen => f.write_str("RIGHT_PAREN ) null"),
             Token::LBrace => f.write_str("LEFT_BRACE { null"),
             Token::RBrace => f.write_str("RIGHT_BRACE } null"),
+            Token::LBracket => f.write_str("LEFT_BRACKET [ null"),
+            Token::RBracket => f.write_str("RIGHT_BRACKET ] null"),
             Token::STAR => f.write_str("STAR * null"),
             Token::DOT => f.write_str("DOT . null"),
             Token::COMMA => f.write_str("COMMA , null"),
+            Token::COLON => f.write_str("COLON : null"),
+            Token::QUESTION => f.write_str("QUESTION ? null"),
+            Token::ARROW => f.write_str("ARROW => null"),
+            Token::DOTDOTDOT => f.write_str("DOT_DOT_DOT ... null"),
+            Token::DOTDOT => f.write_str("DOT_DOT .. null"),
+            Token::PIPE => f.write_str("PIPE | null"),
             Token::PLUS => f.write_str("PLUS + null"),
             Token::MINUS => f.write_str("MINUS - null"),
             Token::SEMICOLON => f.write_str("SEMICOLON ; null"),
@@ -195,30 +388,63 @@ impl std::fmt::Debug for Token {
             Token::GREATER => f.write_str("GREATER > null"),
             Token::GREATEREQUAL => f.write_str("GREATER_EQUAL >= null"),
             Token::SLASH => f.write_str("SLASH / null"),
-            Token::COMMENT(_) => f.write_str("COMMENT  null"),
-            Token::StringLiteral(s) => {
-                // TODO: remove unsafe
-                let string =
-                    unsafe { String::from_str(std::str::from_utf8_unchecked(&s)).unwrap() };
-                f.write_fmt(format_args!("STRING \"{string}\" {string}"))
-            }
+            Token::STARSTAR => f.write_str("STAR_STAR ** null"),
+            Token::PERCENT => f.write_str("PERCENT % null"),
+            Token::TILDESLASH => f.write_str("TILDE_SLASH ~/ null"),
+            Token::PLUSEQUAL => f.write_str("PLUS_EQUAL += null"),
+            Token::MINUSEQUAL => f.write_str("MINUS_EQUAL -= null"),
+            Token::STAREQUAL => f.write_str("STAR_EQUAL *= null"),
+            Token::SLASHEQUAL => f.write_str("SLASH_EQUAL /= null"),
+            Token::PLUSPLUS => f.write_str("PLUS_PLUS ++ null"),
+            Token::MINUSMINUS => f.write_str("MINUS_MINUS -- null"),
+            Token::COMMENT(b) => f.write_fmt(format_args!(
+                "COMMENT {:?} null",
+                String::from_utf8_lossy(b)
+            )),
+            Token::WHITESPACE(b) => f.write_fmt(format_args!(
+                "WHITESPACE {:?} null",
+                String::from_utf8_lossy(b)
+            )),
+            Token::StringLiteral(value, lexeme) => f.write_fmt(format_args!(
+                "STRING \"{}\" {}",
+                String::from_utf8_lossy(lexeme),
+                String::from_utf8_lossy(value)
+            )),
+            Token::StringInterpolation(value, lexeme) => f.write_fmt(format_args!(
+                "STRING_INTERPOLATION \"{}\" {}",
+                String::from_utf8_lossy(lexeme),
+                String::from_utf8_lossy(value)
+            )),
+            Token::StringInterpolationMiddle(value, lexeme) => f.write_fmt(format_args!(
+                "STRING_INTERPOLATION_MIDDLE \"{}\" {}",
+                String::from_utf8_lossy(lexeme),
+                String::from_utf8_lossy(value)
+            )),
+            Token::StringInterpolationEnd(value, lexeme) => f.write_fmt(format_args!(
+                "STRING_INTERPOLATION_END \"{}\" {}",
+                String::from_utf8_lossy(lexeme),
+                String::from_utf8_lossy(value)
+            )),
             Token::NumberLiteral(number, bytes) => f.write_fmt(format_args!(
                 "NUMBER {} {:?}",
-                String::from_str(std::str::from_utf8(bytes.as_ref()).unwrap()).unwrap(),
+                String::from_utf8_lossy(bytes),
                 number
             )),
-            Token::Identifier(identifier_bytes) => f.write_fmt(format_args!(
-                // TODO: remove unwraps.
+            Token::Identifier(_, identifier_bytes) => f.write_fmt(format_args!(
                 "IDENTIFIER {} null",
-                String::from_str(std::str::from_utf8(identifier_bytes.as_ref()).unwrap()).unwrap()
+                String::from_utf8_lossy(identifier_bytes)
             )),
             Token::And => f.write_str("AND and null"),
+            Token::Break => f.write_str("BREAK break null"),
             Token::Class => f.write_str("CLASS class null"),
+            Token::Continue => f.write_str("CONTINUE continue null"),
             Token::Else => f.write_str("ELSE else null"),
+            Token::Enum => f.write_str("ENUM enum null"),
             Token::False => f.write_str("FALSE false null"),
             Token::For => f.write_str("FOR for null"),
             Token::Fun => f.write_str("FUN fun null"),
             Token::If => f.write_str("IF if null"),
+            Token::Match => f.write_str("MATCH match null"),
             Token::Nil => f.write_str("NIL nil null"),
             Token::Or => f.write_str("OR or null"),
             Token::Print => f.write_str("PRINT print null"),
@@ -233,78 +459,614 @@ impl std::fmt::Debug for Token {
     }
 }
 
+/// Number of bytes asked from the reader at a time when scanning a streamed source.
+const CHUNK_SIZE: usize = 8 * 1024;
+
 pub(crate) struct Scanner {
     _source: Bytes,
+    // NOTE: a streamed source can only be scanned once, the first `iter()` takes the reader.
+    reader: RefCell<Option<Box<dyn Read>>>,
+    preserve_trivia: bool,
+    interner: Rc<RefCell<Interner>>,
 }
 
 impl Scanner {
     pub(crate) fn new(source: String) -> Self {
         Self {
             _source: Bytes::from(source),
+            reader: RefCell::new(None),
+            preserve_trivia: false,
+            interner: Rc::default(),
+        }
+    }
+
+    /// A scanner that reads the source from `reader` in chunks while scanning, instead of needing
+    /// all of it upfront. Only the bytes of the token being scanned are held on to.
+    pub(crate) fn from_reader<R: Read + 'static>(reader: R) -> Self {
+        Self {
+            _source: Bytes::new(),
+            reader: RefCell::new(Some(Box::new(reader))),
+            preserve_trivia: false,
+            interner: Rc::default(),
+        }
+    }
+
+    /// A scanner that also yields whitespace runs and comments as `Token::WHITESPACE` and
+    /// `Token::COMMENT`, so that the lexemes of all the tokens add up to the original source.
+    /// NOTE: the bytes of a lexical error are not part of any token.
+    pub(crate) fn with_trivia(source: String) -> Self {
+        Self {
+            _source: Bytes::from(source),
+            reader: RefCell::new(None),
+            preserve_trivia: true,
+            interner: Rc::default(),
         }
     }
 
+    /// The interner the scanned identifiers are interned in.
+    pub(crate) fn interner(&self) -> Rc<RefCell<Interner>> {
+        self.interner.clone()
+    }
+
     pub(crate) fn iter(&self) -> TokenIterator {
         TokenIterator {
             remaining: self._source.clone(),
+            buffer: self._source.clone(),
+            buffer_start: 0,
+            token_start: 0,
+            reader: self.reader.borrow_mut().take(),
+            read_error: None,
             reached_eof: false,
             line: 1,
+            column: 1,
+            offset: 0,
+            interpolations: vec![],
+            preserve_trivia: self.preserve_trivia,
+            in_preamble: true,
+            interner: self.interner.clone(),
+        }
+    }
+}
+
+/// Location of a token in the source. `start` and `end` are byte offsets (`end` is exclusive),
+/// `line` and `column` are 1-based and point at the first character of the token. Columns count
+/// characters, not bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SpannedToken {
+    pub(crate) token: Token,
+    pub(crate) span: Span,
+    /// The exact source bytes the token was scanned from.
+    pub(crate) lexeme: Bytes,
+}
+
+impl Span {
+    fn to_json(self) -> String {
+        format!(
+            "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{}}}",
+            self.start, self.end, self.line, self.column
+        )
+    }
+}
+
+impl SpannedToken {
+    /// The token as a single line JSON object, for `tokenize --format json`.
+    pub(crate) fn to_json(&self) -> String {
+        let literal = match &self.token {
+            Token::StringLiteral(value, _)
+            | Token::StringInterpolation(value, _)
+            | Token::StringInterpolationMiddle(value, _)
+            | Token::StringInterpolationEnd(value, _) => {
+                json_string(&String::from_utf8_lossy(value))
+            }
+            // NOTE: JSON has no representation for infinity, which is what `1e999` scans to.
+            Token::NumberLiteral(number, _) if number.is_finite() => format!("{number:?}"),
+            _ => "null".to_string(),
+        };
+        format!(
+            "{{\"kind\":\"{}\",\"lexeme\":{},\"literal\":{},\"position\":{}}}",
+            self.token.kind_name(),
+            json_string(&String::from_utf8_lossy(&self.lexeme)),
+            literal,
+            self.span.to_json()
+        )
+    }
+}
+
+/// Quotes `s` as a JSON string, escaping what JSON requires to be escaped.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if ch.is_control() => json.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => json.push(ch),
         }
     }
+    json.push('"');
+    json
 }
 
 pub(crate) struct TokenIterator {
     remaining: Bytes,
+    // NOTE: `remaining` is always the tail of `buffer`, which starts at the source offset
+    // `buffer_start` and is only ever refilled from the reader, when there is one.
+    buffer: Bytes,
+    buffer_start: usize,
+    token_start: usize,
+    reader: Option<Box<dyn Read>>,
+    read_error: Option<std::io::Error>,
     reached_eof: bool,
     line: u32,
+    column: u32,
+    offset: usize,
+    // NOTE: one entry per string interpolation we are currently inside of, counting the `{`
+    // opened within its expression so that we know which `}` resumes the string.
+    interpolations: Vec<u32>,
+    preserve_trivia: bool,
+    in_preamble: bool,
+    interner: Rc<RefCell<Interner>>,
 }
 
 impl TokenIterator {
     pub(crate) fn get_curr_line(&self) -> u32 {
         self.line
     }
-    fn skip_whitespaces(&mut self) {
-        loop {
-            if self.remaining.len() == 0 {
-                return;
+
+    /// Makes sure that at least `len` unconsumed bytes are buffered, unless the reader runs out
+    /// first. Bytes before the start of the current token are dropped from the buffer.
+    fn fill_buffer(&mut self, len: usize) {
+        if self.remaining.len() >= len {
+            return;
+        }
+        let Some(mut reader) = self.reader.take() else {
+            return;
+        };
+        let keep_from = self.token_start - self.buffer_start;
+        let consumed = self.offset - self.token_start;
+        let mut buffer = BytesMut::with_capacity(self.buffer.len() - keep_from + CHUNK_SIZE);
+        buffer.put(&self.buffer[keep_from..]);
+        let mut reached_end = false;
+        while buffer.len() - consumed < len {
+            let filled = buffer.len();
+            // NOTE: the chunk grows with the token, so that scanning a huge string literal
+            // doesn't copy it over and over again.
+            buffer.resize(filled + CHUNK_SIZE.max(filled), 0);
+            let result = reader.read(&mut buffer[filled..]);
+            buffer.truncate(filled + *result.as_ref().unwrap_or(&0));
+            match result {
+                Ok(0) => reached_end = true,
+                Ok(_) => continue,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.read_error = Some(e);
+                    reached_end = true;
+                }
             }
-            let ch = self.remaining.slice(0..1);
-            if *ch == *b"\n" {
+            break;
+        }
+        if !reached_end {
+            self.reader = Some(reader);
+        }
+        self.buffer = buffer.freeze();
+        self.buffer_start = self.token_start;
+        self.remaining = self.buffer.slice(consumed..);
+    }
+
+    /// The unconsumed byte at `index`, reading more of the source if it isn't buffered yet.
+    fn byte_at(&mut self, index: usize) -> Option<u8> {
+        self.fill_buffer(index + 1);
+        self.remaining.get(index).copied()
+    }
+
+    /// The source bytes from the offset `start` up to the current offset.
+    fn lexeme_from(&self, start: usize) -> Bytes {
+        self.buffer
+            .slice(start - self.buffer_start..self.offset - self.buffer_start)
+    }
+
+    /// Consumes `count` bytes, keeping the line/column/offset bookkeeping in sync.
+    fn advance(&mut self, count: usize) {
+        for byte in self.remaining[..count].iter() {
+            if *byte == b'\n' {
                 self.line += 1;
-                self.remaining = self.remaining.slice(1..);
-                continue;
+                self.column = 1;
+            } else if *byte & 0xC0 != 0x80 {
+                // NOTE: continuation bytes of a multi-byte character don't start a new column.
+                self.column += 1;
+            }
+        }
+        self.offset += count;
+        self.remaining = self.remaining.slice(count..);
+    }
+
+    /// Scans a run of whitespace or a single comment, `None` if the next token isn't trivia.
+    fn scan_trivia(&mut self) -> Option<Result<Token, LexicalErrorKind>> {
+        let start = self.offset;
+        match (self.byte_at(0), self.byte_at(1)) {
+            (Some(b' ' | b'\t' | b'\n' | b'\r'), _) => {
+                let mut len = 1;
+                while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.byte_at(len) {
+                    len += 1;
+                }
+                self.advance(len);
+                Some(Ok(Token::WHITESPACE(self.lexeme_from(start))))
+            }
+            (Some(b'/'), Some(b'/')) => {
+                self.skip_line_comment();
+                Some(Ok(Token::COMMENT(self.lexeme_from(start))))
             }
-            if *ch == *b" " || *ch == *b"\t" {
-                self.remaining = self.remaining.slice(1..);
-            } else {
-                break;
+            (Some(b'/'), Some(b'*')) => Some(
+                self.skip_block_comment()
+                    .map(|_| Token::COMMENT(self.lexeme_from(start))),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Scans a UTF-8 byte order mark or a `#!` shebang line at the very start of the source.
+    fn scan_preamble(&mut self) -> Option<Token> {
+        if !self.in_preamble {
+            return None;
+        }
+        self.fill_buffer(3);
+        let start = self.offset;
+        if self.offset == 0 && self.remaining.starts_with(b"\xEF\xBB\xBF") {
+            // NOTE: the BOM is invisible, so it only moves the offset and not the column.
+            self.offset += 3;
+            self.remaining = self.remaining.slice(3..);
+            return Some(Token::WHITESPACE(self.lexeme_from(start)));
+        }
+        self.in_preamble = false;
+        if self.remaining.starts_with(b"#!") {
+            self.skip_line_comment();
+            return Some(Token::COMMENT(self.lexeme_from(start)));
+        }
+        None
+    }
+
+    fn skip_line_comment(&mut self) {
+        let mut comment_len = 0;
+        while !matches!(self.byte_at(comment_len), None | Some(b'\n')) {
+            comment_len += 1;
+        }
+        self.advance(comment_len);
+    }
+
+    /// Skips a `/* ... */` comment. Block comments nest, so every `/*` inside the comment needs
+    /// its own `*/`.
+    fn skip_block_comment(&mut self) -> Result<(), LexicalErrorKind> {
+        let mut depth = 0;
+        loop {
+            match (self.byte_at(0), self.byte_at(1)) {
+                (None, _) => return Err(LexicalErrorKind::UnterminatedBlockComment),
+                (Some(b'/'), Some(b'*')) => {
+                    depth += 1;
+                    self.advance(2);
+                }
+                (Some(b'*'), Some(b'/')) => {
+                    depth -= 1;
+                    self.advance(2);
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => self.advance(1),
             }
         }
     }
+
+    /// Returns the bytes of the next character, which can be up to 4 bytes long for UTF-8.
     fn next_byte(&mut self) -> Option<Bytes> {
-        self.skip_whitespaces();
+        self.fill_buffer(4);
         if self.remaining.len() == 0 {
             return None;
         }
-        Some(self.remaining.slice(0..1))
+        let len = self.remaining.len().min(4);
+        Some(self.remaining.slice(0..len))
+    }
+
+    /// Scans the rest of a string literal, the opening quote must already be consumed. This is
+    /// also used to resume a string after the `}` closing an interpolated expression, in which
+    /// case `after_interpolation` is set and the part is scanned as a middle or end part.
+    fn scan_string(&mut self, after_interpolation: bool) -> Result<Token, LexicalErrorKind> {
+        let start = self.offset;
+        // NOTE: the value is only copied out of the source once the first escape sequence shows up.
+        let mut value: Option<BytesMut> = None;
+        let mut error = None;
+        loop {
+            let Some(byte) = self.byte_at(0) else {
+                return Err(LexicalErrorKind::UnterminatedString);
+            };
+            match byte {
+                b'"' => {
+                    let lexeme = self.lexeme_from(start);
+                    if std::str::from_utf8(&lexeme).is_err() {
+                        error.get_or_insert(LexicalErrorKind::InvalidUtf8);
+                    }
+                    self.advance(1);
+                    if let Some(e) = error {
+                        return Err(e);
+                    }
+                    let value = value.map_or_else(|| lexeme.clone(), |value| value.freeze());
+                    if after_interpolation {
+                        return Ok(Token::StringInterpolationEnd(value, lexeme));
+                    }
+                    return Ok(Token::StringLiteral(value, lexeme));
+                }
+                b'$' if self.byte_at(1) == Some(b'{') => {
+                    let lexeme = self.lexeme_from(start);
+                    if std::str::from_utf8(&lexeme).is_err() {
+                        error.get_or_insert(LexicalErrorKind::InvalidUtf8);
+                    }
+                    self.advance(2);
+                    self.interpolations.push(0);
+                    if let Some(e) = error {
+                        return Err(e);
+                    }
+                    let value = value.map_or_else(|| lexeme.clone(), |value| value.freeze());
+                    if after_interpolation {
+                        return Ok(Token::StringInterpolationMiddle(value, lexeme));
+                    }
+                    return Ok(Token::StringInterpolation(value, lexeme));
+                }
+                b'\\' => {
+                    let lexeme = self.lexeme_from(start);
+                    let value = value.get_or_insert_with(|| BytesMut::from(lexeme.as_ref()));
+                    match self.scan_escape_sequence() {
+                        Ok(ch) => value.put(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                        }
+                    }
+                }
+                byte => {
+                    if let Some(value) = value.as_mut() {
+                        value.put_u8(byte);
+                    }
+                    self.advance(1);
+                }
+            }
+        }
+    }
+
+    fn scan_escape_sequence(&mut self) -> Result<char, LexicalErrorKind> {
+        let escaped = match self.byte_at(1) {
+            Some(b'n') => '\n',
+            Some(b't') => '\t',
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'$') => '$',
+            Some(b'u') => return self.scan_unicode_escape(),
+            _ => {
+                let len = self.remaining.len().min(2);
+                let sequence = String::from_utf8_lossy(&self.remaining[..len]).into_owned();
+                // NOTE: only the backslash is consumed, whatever follows is still part of the string.
+                self.advance(1);
+                return Err(LexicalErrorKind::InvalidEscapeSequence { sequence });
+            }
+        };
+        self.advance(2);
+        Ok(escaped)
+    }
+
+    /// Scans a `\u{...}` escape holding 1 to 6 hex digits of a unicode scalar value.
+    fn scan_unicode_escape(&mut self) -> Result<char, LexicalErrorKind> {
+        let mut len = 2;
+        if self.byte_at(len) == Some(b'{') {
+            len += 1;
+            let digits_start = len;
+            let mut code_point: u32 = 0;
+            while let Some(digit) = self
+                .byte_at(len)
+                .and_then(|byte| (byte as char).to_digit(16))
+            {
+                code_point = code_point.saturating_mul(16).saturating_add(digit);
+                len += 1;
+            }
+            let digit_count = len - digits_start;
+            if self.byte_at(len) == Some(b'}') && (1..=6).contains(&digit_count) {
+                len += 1;
+                if let Some(ch) = char::from_u32(code_point) {
+                    self.advance(len);
+                    return Ok(ch);
+                }
+            }
+        }
+        let sequence = String::from_utf8_lossy(&self.remaining[..len]).into_owned();
+        self.advance(len);
+        Err(LexicalErrorKind::InvalidEscapeSequence { sequence })
+    }
+
+    /// Scans `123`, `1_000.5`, `1e-9`, `0xFF` and `0b1010` style number literals.
+    fn scan_number(&mut self) -> Result<Token, LexicalErrorKind> {
+        let radix = match (self.byte_at(0), self.byte_at(1)) {
+            (Some(b'0'), Some(b'x' | b'X')) => Some(16),
+            (Some(b'0'), Some(b'b' | b'B')) => Some(2),
+            _ => None,
+        };
+        // NOTE: like an exponent, a prefix without a digit after it isn't part of the number, so
+        // `0bar` is still `0` followed by `bar`.
+        let is_radix_digit = |byte: u8, radix: u32| (byte as char).is_digit(radix);
+        let radix = radix.filter(|radix| {
+            self.byte_at(2)
+                .is_some_and(|byte| is_radix_digit(byte, *radix))
+        });
+        let (len, number) = match radix {
+            Some(radix) => {
+                let mut len = 2;
+                while self
+                    .byte_at(len)
+                    .is_some_and(|byte| is_radix_digit(byte, radix) || byte == b'_')
+                {
+                    len += 1;
+                }
+                (len, parse_radix_number(&self.remaining[2..len], radix))
+            }
+            None => {
+                let mut len = self.digits_end(0);
+                if self.byte_at(len) == Some(b'.')
+                    && self
+                        .byte_at(len + 1)
+                        .is_some_and(|byte| byte.is_ascii_digit())
+                {
+                    len = self.digits_end(len + 1);
+                }
+                if let Some(b'e' | b'E') = self.byte_at(len) {
+                    let sign_len = usize::from(matches!(self.byte_at(len + 1), Some(b'+' | b'-')));
+                    if self
+                        .byte_at(len + 1 + sign_len)
+                        .is_some_and(|byte| byte.is_ascii_digit())
+                    {
+                        len = self.digits_end(len + 1 + sign_len);
+                    }
+                }
+                (len, parse_decimal_number(&self.remaining[..len]))
+            }
+        };
+        let bytes = self.remaining.slice(0..len);
+        self.advance(len);
+        match number {
+            Some(number) => Ok(Token::NumberLiteral(number, bytes)),
+            None => Err(LexicalErrorKind::MalformedNumber {
+                lexeme: String::from_utf8_lossy(&bytes).into_owned(),
+            }),
+        }
+    }
+
+    /// End of the run of decimal digits and `_` separators starting at `from`.
+    fn digits_end(&mut self, from: usize) -> usize {
+        let mut end = from;
+        while self
+            .byte_at(end)
+            .is_some_and(|byte| byte.is_ascii_digit() || byte == b'_')
+        {
+            end += 1;
+        }
+        end
     }
 
-    fn peek_token(&self) -> Option<Bytes> {
-        if self.remaining.len() == 1 {
+    fn peek_token(&mut self) -> Option<Bytes> {
+        self.fill_buffer(2);
+        if self.remaining.len() <= 1 {
             return None;
         }
         Some(self.remaining.slice(1..2))
     }
 }
 
+/// Decodes the character at the start of `bytes`, `None` if it isn't valid UTF-8.
+fn decode_char(bytes: &[u8]) -> Option<char> {
+    let width = match bytes.first()? {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return None,
+    };
+    let encoded = std::str::from_utf8(bytes.get(..width)?).ok()?;
+    encoded.chars().next()
+}
+
+/// A `_` separator is only allowed between two digits.
+fn has_valid_separators(digits: &[u8], is_digit: impl Fn(u8) -> bool) -> bool {
+    digits.iter().enumerate().all(|(index, byte)| {
+        *byte != b'_'
+            || (index > 0
+                && index + 1 < digits.len()
+                && is_digit(digits[index - 1])
+                && is_digit(digits[index + 1]))
+    })
+}
+
+fn parse_radix_number(digits: &[u8], radix: u32) -> Option<f64> {
+    let is_digit = |byte: u8| (byte as char).is_digit(radix);
+    if digits.is_empty() || !has_valid_separators(digits, is_digit) {
+        return None;
+    }
+    digits
+        .iter()
+        .filter(|byte| **byte != b'_')
+        .try_fold(0f64, |number, byte| {
+            let digit = (*byte as char).to_digit(radix)?;
+            Some(number * radix as f64 + digit as f64)
+        })
+}
+
+fn parse_decimal_number(lexeme: &[u8]) -> Option<f64> {
+    if !has_valid_separators(lexeme, |byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let digits: String = lexeme
+        .iter()
+        .filter(|byte| **byte != b'_')
+        .map(|byte| *byte as char)
+        .collect();
+    f64::from_str(&digits).ok()
+}
+
 impl Iterator for TokenIterator {
-    type Item = Result<Token, LexicalError>;
+    type Item = Result<SpannedToken, LexicalError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.reached_eof {
             return None;
         }
+        loop {
+            let (start, line, column) = (self.offset, self.line, self.column);
+            self.token_start = start;
+            if let Some(e) = self.read_error.take() {
+                let span = Span {
+                    start,
+                    end: start,
+                    line,
+                    column,
+                };
+                let kind = LexicalErrorKind::Io(e.to_string());
+                return Some(Err(LexicalError { kind, span }));
+            }
+            let token = match self.scan_preamble().map(Ok).or_else(|| self.scan_trivia()) {
+                Some(Ok(_)) if !self.preserve_trivia => continue,
+                Some(trivia) => trivia,
+                None => self.scan_token()?,
+            };
+            let span = Span {
+                start,
+                end: self.offset,
+                line,
+                column,
+            };
+            return Some(match token {
+                Ok(token) => Ok(SpannedToken {
+                    token,
+                    span,
+                    lexeme: self.lexeme_from(start),
+                }),
+                Err(kind) => Err(LexicalError { kind, span }),
+            });
+        }
+    }
+}
+
+impl TokenIterator {
+    fn scan_token(&mut self) -> Option<Result<Token, LexicalErrorKind>> {
+        self.fill_buffer(1);
         if self.remaining.len() == 0 {
+            if let Some(e) = self.read_error.take() {
+                return Some(Err(LexicalErrorKind::Io(e.to_string())));
+            }
             self.reached_eof = true;
             return Some(Ok(Token::EOF));
         }
@@ -313,46 +1075,126 @@ impl Iterator for TokenIterator {
             self.reached_eof = true;
             return Some(Ok(Token::EOF));
         };
-        let ch = slice[0] as char;
+        let Some(ch) = decode_char(&slice) else {
+            self.advance(1);
+            return Some(Err(LexicalErrorKind::InvalidUtf8));
+        };
         let token_to_return = match ch {
             '(' => {
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 Some(Ok(Token::LParen))
             }
             ')' => {
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 Some(Ok(Token::RParen))
             }
+            ':' => {
+                self.advance(1);
+                Some(Ok(Token::COLON))
+            }
+            '?' => {
+                self.advance(1);
+                Some(Ok(Token::QUESTION))
+            }
+            '|' => {
+                self.advance(1);
+                Some(Ok(Token::PIPE))
+            }
+            '[' => {
+                self.advance(1);
+                Some(Ok(Token::LBracket))
+            }
+            ']' => {
+                self.advance(1);
+                Some(Ok(Token::RBracket))
+            }
             '{' => {
-                self.remaining = self.remaining.slice(1..);
+                if let Some(open_braces) = self.interpolations.last_mut() {
+                    *open_braces += 1;
+                }
+                self.advance(1);
                 Some(Ok(Token::LBrace))
             }
             '}' => {
-                self.remaining = self.remaining.slice(1..);
-                Some(Ok(Token::RBrace))
+                self.advance(1);
+                match self.interpolations.last_mut() {
+                    Some(0) => {
+                        self.interpolations.pop();
+                        Some(self.scan_string(true))
+                    }
+                    Some(open_braces) => {
+                        *open_braces -= 1;
+                        Some(Ok(Token::RBrace))
+                    }
+                    None => Some(Ok(Token::RBrace)),
+                }
             }
             '*' => {
-                self.remaining = self.remaining.slice(1..);
+                if self.byte_at(1) == Some(b'*') {
+                    self.advance(2);
+                    return Some(Ok(Token::STARSTAR));
+                }
+                if self.byte_at(1) == Some(b'=') {
+                    self.advance(2);
+                    return Some(Ok(Token::STAREQUAL));
+                }
+                self.advance(1);
                 Some(Ok(Token::STAR))
             }
+            '%' => {
+                self.advance(1);
+                Some(Ok(Token::PERCENT))
+            }
+            '~' if self.byte_at(1) == Some(b'/') => {
+                self.advance(2);
+                Some(Ok(Token::TILDESLASH))
+            }
             '.' => {
-                self.remaining = self.remaining.slice(1..);
+                if self.byte_at(1) == Some(b'.') {
+                    if self.byte_at(2) == Some(b'.') {
+                        self.advance(3);
+                        return Some(Ok(Token::DOTDOTDOT));
+                    }
+                    self.advance(2);
+                    return Some(Ok(Token::DOTDOT));
+                }
+                self.advance(1);
                 Some(Ok(Token::DOT))
             }
             ',' => {
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 Some(Ok(Token::COMMA))
             }
-            '+' => {
-                self.remaining = self.remaining.slice(1..);
-                Some(Ok(Token::PLUS))
-            }
-            '-' => {
-                self.remaining = self.remaining.slice(1..);
-                Some(Ok(Token::MINUS))
-            }
+            '+' => match self.byte_at(1) {
+                Some(b'+') => {
+                    self.advance(2);
+                    Some(Ok(Token::PLUSPLUS))
+                }
+                Some(b'=') => {
+                    self.advance(2);
+                    Some(Ok(Token::PLUSEQUAL))
+                }
+                _ => {
+                    self.advance(1);
+                    Some(Ok(Token::PLUS))
+                }
+            },
+            '-' => match self.byte_at(1) {
+                Some(b'-') => {
+                    self.advance(2);
+                    Some(Ok(Token::MINUSMINUS))
+                }
+                Some(b'=') => {
+                    self.advance(2);
+                    Some(Ok(Token::MINUSEQUAL))
+                }
+                _ => {
+                    self.advance(1);
+                    Some(Ok(Token::MINUS))
+                }
+            },
             ';' => {
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 Some(Ok(Token::SEMICOLON))
             }
             '=' => {
@@ -361,178 +1203,116 @@ impl Iterator for TokenIterator {
 
                 let bytes = match peeked_token {
                     None => {
-                        self.remaining = self.remaining.slice(1..);
+                        self.advance(1);
                         return Some(Ok(Token::EQUAL));
                     }
                     Some(bytes) => bytes,
                 };
                 if let b"=" = bytes.as_ref() {
-                    self.remaining = self.remaining.slice(2..);
+                    self.advance(2);
                     return Some(Ok(Token::EQUALEQUAL));
                 }
-                self.remaining = self.remaining.slice(1..);
+                if let b">" = bytes.as_ref() {
+                    self.advance(2);
+                    return Some(Ok(Token::ARROW));
+                }
+                self.advance(1);
                 return Some(Ok(Token::EQUAL));
             }
             '!' => {
                 let peeked_token = self.peek_token();
                 let bytes = match peeked_token {
                     None => {
-                        self.remaining = self.remaining.slice(1..);
+                        self.advance(1);
                         return Some(Ok(Token::BANG));
                     }
                     Some(bytes) => bytes,
                 };
                 if let b"=" = bytes.as_ref() {
-                    self.remaining = self.remaining.slice(2..);
+                    self.advance(2);
                     return Some(Ok(Token::BANGEQUAL));
                 }
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 return Some(Ok(Token::BANG));
             }
             '<' => {
                 let peeked_token = self.peek_token();
                 let bytes = match peeked_token {
                     None => {
-                        self.remaining = self.remaining.slice(1..);
+                        self.advance(1);
                         return Some(Ok(Token::LESS));
                     }
                     Some(bytes) => bytes,
                 };
                 if let b"=" = bytes.as_ref() {
-                    self.remaining = self.remaining.slice(2..);
+                    self.advance(2);
                     return Some(Ok(Token::LESSEQUAL));
                 }
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 return Some(Ok(Token::LESS));
             }
             '>' => {
                 let peeked_token = self.peek_token();
                 let bytes = match peeked_token {
                     None => {
-                        self.remaining = self.remaining.slice(1..);
+                        self.advance(1);
                         return Some(Ok(Token::GREATER));
                     }
                     Some(bytes) => bytes,
                 };
                 if let b"=" = bytes.as_ref() {
-                    self.remaining = self.remaining.slice(2..);
+                    self.advance(2);
                     return Some(Ok(Token::GREATEREQUAL));
                 }
-                self.remaining = self.remaining.slice(1..);
+                self.advance(1);
                 return Some(Ok(Token::GREATER));
             }
             '/' => {
-                let peeked_token = self.peek_token();
-                let bytes = match peeked_token {
-                    None => {
-                        self.remaining = self.remaining.slice(1..);
-                        return Some(Ok(Token::SLASH));
-                    }
-                    Some(bytes) => bytes,
-                };
-                if let b"/" = bytes.as_ref() {
-                    self.remaining = self.remaining.slice(2..);
-                    loop {
-                        let peeked_token = self.peek_token();
-                        let bytes = match peeked_token {
-                            None => {
-                                self.reached_eof = true;
-                                return Some(Ok(Token::EOF));
-                            }
-                            Some(bytes) => bytes,
-                        };
-
-                        if let b"\n" = bytes.as_ref() {
-                            self.remaining = self.remaining.slice(1..);
-                            return self.next();
-                        } else {
-                            self.remaining = self.remaining.slice(1..);
-                        }
-                    }
-                } else {
-                    self.remaining = self.remaining.slice(1..);
-                    Some(Ok(Token::SLASH))
+                if self.byte_at(1) == Some(b'=') {
+                    self.advance(2);
+                    return Some(Ok(Token::SLASHEQUAL));
                 }
+                self.advance(1);
+                Some(Ok(Token::SLASH))
             }
             '\"' => {
-                self.remaining = self.remaining.slice(1..);
-                let mut size_of_str: usize = 0;
-                let remaining_size = self.remaining.len();
-                loop {
-                    if size_of_str == remaining_size {
-                        self.remaining = self.remaining.slice(remaining_size..);
-                        return Some(Err(LexicalError::UnterminatedString { line: self.line }));
-                    }
-                    let x = self.remaining[size_of_str];
-                    if *b"\"" == [x] {
-                        let bytes = self.remaining.slice(0..size_of_str);
-                        // TODO: remove unwrap and unsafe
-                        self.remaining = self.remaining.slice(size_of_str + 1..);
-                        return Some(Ok(Token::StringLiteral(bytes.clone())));
-                    } else {
-                        size_of_str += 1;
-                    }
-                }
+                self.advance(1);
+                Some(self.scan_string(false))
             }
-            '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                let mut digit_count = 1;
-                let mut is_float = false;
-                loop {
-                    if self.remaining.slice(digit_count..).len() == 0 {
-                        break;
-                    }
-                    let ch = self.remaining[digit_count] as char;
-                    if ch.is_digit(10) {
-                        digit_count += 1;
-                    } else if ch == '.' && is_float == false {
-                        if self.remaining.slice(digit_count + 1..).len() == 0 {
-                            break;
-                        }
-                        let ch = self.remaining[digit_count + 1] as char;
-                        if !ch.is_digit(10) {
-                            break;
-                        } else {
-                            digit_count += 1;
-                            is_float = true;
-                        }
-                    } else {
+            '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => Some(self.scan_number()),
+            ch if is_xid_start(ch) || ch == '_' => {
+                let mut identifier_len = ch.len_utf8();
+                while let Some(ch) = {
+                    self.fill_buffer(identifier_len + 4);
+                    decode_char(&self.remaining[identifier_len..])
+                } {
+                    if !is_xid_continue(ch) {
                         break;
                     }
+                    identifier_len += ch.len_utf8();
                 }
-                let bytes = self.remaining.slice(0..digit_count);
-                let number = f64::from_str(std::str::from_utf8(bytes.as_ref()).unwrap()).unwrap();
-                self.remaining = self.remaining.slice(digit_count..);
-                Some(Ok(Token::NumberLiteral(number, bytes)))
-            }
-            ch if ch.is_alphabetic() || ch == '_' => {
-                let mut identifier_len = 1;
-                loop {
-                    if self.remaining.slice(identifier_len..).len() == 0 {
-                        break;
-                    }
-                    let ch = self.remaining[identifier_len] as char;
-                    if ch.is_alphanumeric() || ch == '_' {
-                        identifier_len += 1;
-                    } else {
-                        break;
+                let bytes = &self.remaining[..identifier_len];
+                let keyword = std::str::from_utf8(bytes)
+                    .ok()
+                    .and_then(|identifier| KEYWORDS.get(identifier));
+                let token = match keyword {
+                    Some(keyword) => Some(Ok(keyword.clone())),
+                    None => {
+                        let mut interner = self.interner.borrow_mut();
+                        let symbol = interner.intern(bytes);
+                        Some(Ok(Token::Identifier(
+                            symbol,
+                            interner.resolve(symbol).clone(),
+                        )))
                     }
-                }
-                let bytes = self.remaining.slice(0..identifier_len);
-                let utf_bytes = unsafe { std::str::from_utf8_unchecked(bytes.as_ref()) };
-                let token = if KEYWORDS.contains_key(utf_bytes) {
-                    let x = KEYWORDS.get(utf_bytes).unwrap().clone();
-                    Some(Ok(x))
-                } else {
-                    Some(Ok(Token::Identifier(bytes)))
                 };
-                self.remaining = self.remaining.slice(identifier_len..);
+                self.advance(identifier_len);
                 token
             }
             unexpected => {
-                self.remaining = self.remaining.slice(1..);
+                self.advance(unexpected.len_utf8());
                 let ch = unexpected;
-                let line = self.line;
-                Some(Err(LexicalError::UnExpectedToken { ch, line }))
+                Some(Err(LexicalErrorKind::UnExpectedToken { ch }))
             }
         };
         token_to_return